@@ -1,25 +1,37 @@
-use wesl::{CompileOptions, ManglerKind, StandardResolver, Wesl};
+include!("src/render/shader/compiler.rs");
+include!("src/render/shader/constants.rs");
+
+#[macro_use]
+#[path = "src/macros.rs"]
+mod macros;
+#[path = "src/render/raymarching/kind.rs"]
+#[allow(dead_code)]
+mod raymarching_kind;
+#[path = "src/render/shape/kind.rs"]
+#[allow(dead_code)]
+mod shape_kind;
+
+use macros::EnumShaderConstants;
 
 fn main() {
-    let wesl = {
-        let resolver = StandardResolver::new("src/shaders");
+    // Listing anything here stops cargo from rerunning on every change in the package
+    println!("cargo:rerun-if-changed=src/macros.rs");
+    println!("cargo:rerun-if-changed=src/render/shader/compiler.rs");
+    println!("cargo:rerun-if-changed=src/render/shader/constants.rs");
+    println!("cargo:rerun-if-changed=src/render/shape/kind.rs");
+    println!("cargo:rerun-if-changed=src/render/raymarching/kind.rs");
+    println!("cargo:rerun-if-changed=src/shaders");
 
-        let mut wesl = Wesl::new_barebones().set_custom_resolver(resolver);
-        wesl.set_mangler(ManglerKind::default())
-            .set_options(CompileOptions {
-                imports: true,
-                condcomp: true,
-                lower: true,
-                validate: true,
-                lazy: true,
-                ..Default::default()
-            });
-        wesl
-    };
+    let wesl = wesl_compiler(
+        "src/shaders",
+        enum_modules::<shape_kind::ShapeKind, raymarching_kind::ShapeKind>(),
+    );
     wesl.build_artifact("wallpaper.wesl", "wallpaper");
     wesl.build_artifact("glass.wesl", "glass");
     wesl.build_artifact("raymarching.wesl", "raymarching");
     wesl.build_artifact("glass_shapes.wesl", "glass_shapes");
     wesl.build_artifact("silhouette_sdf.wesl", "silhouette_sdf");
     wesl.build_artifact("light_maps.wesl", "light_maps");
+    wesl.build_artifact("downsample.wesl", "downsample");
+    wesl.build_artifact("backdrop.wesl", "backdrop");
 }
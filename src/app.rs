@@ -1,18 +1,20 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use wgpu::CompositeAlphaMode;
 use winit::{
     application::ApplicationHandler,
     dpi::{PhysicalPosition, PhysicalSize},
-    event::{ElementState, MouseButton, WindowEvent},
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{Key, ModifiersState, NamedKey},
     window::Window,
 };
 
-use crate::render::Renderer;
+use crate::{args::Args, render::Renderer};
 
-#[derive(Default)]
 pub struct App {
+    args: Args,
     env: Option<WgpuEnv>,
+    modifiers: ModifiersState,
 }
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
@@ -80,6 +82,42 @@ impl ApplicationHandler for App {
                 ElementState::Pressed => self.env_mut().mouse_press(),
                 ElementState::Released => self.env_mut().mouse_release(),
             },
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::Space),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => self.env_mut().toggle_wallpaper_playback(),
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(key),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if key == "s" && self.modifiers.control_key() => self.save_scene(),
+            // 1 to 9 hide or show the layer at that position, counting from the bottom
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(key),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                if let Ok(position @ 1..=9) = key.parse::<usize>() {
+                    self.env_mut().toggle_layer(position - 1);
+                }
+            }
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
@@ -88,8 +126,23 @@ impl ApplicationHandler for App {
     }
 }
 impl App {
+    pub fn new(args: Args) -> Self {
+        Self {
+            args,
+            env: None,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+    /// Writes the shapes back to the `--scene` file, or `scene.ron` without one
+    fn save_scene(&self) {
+        let path = self.args.scene.as_deref().unwrap_or(Path::new("scene.ron"));
+        match self.env().renderer.save_scene(path) {
+            Ok(()) => log::info!("saved the scene to {}", path.display()),
+            Err(err) => log::error!("{err}"),
+        }
+    }
     fn init_window(&mut self, window: Window) {
-        self.env = Some(pollster::block_on(WgpuEnv::new(window)));
+        self.env = Some(pollster::block_on(WgpuEnv::new(window, &self.args)));
     }
     fn window(&self) -> &Window {
         &self.env().window
@@ -115,6 +168,7 @@ struct WgpuEnv {
 impl WgpuEnv {
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         // self.pipelines.tick(&self.queue);
+        self.renderer.prepare(&self.device, &self.queue);
 
         let output = self.surface.get_current_texture()?;
         let view = output
@@ -177,7 +231,22 @@ impl WgpuEnv {
     fn mouse_release(&mut self) {
         self.renderer.mouse_release(&self.queue);
     }
-    async fn new(window: Window) -> Self {
+    fn toggle_wallpaper_playback(&mut self) {
+        self.renderer.toggle_wallpaper_playback();
+    }
+    /// Hides or shows the layer at `position` in the stack, counting from the bottom
+    fn toggle_layer(&mut self, position: usize) {
+        let Some(id) = self.renderer.layers().nth(position) else {
+            return;
+        };
+        let enabled = !self.renderer.is_layer_enabled(id);
+        self.renderer.set_layer_enabled(id, enabled);
+        if let Some(kind) = self.renderer.layer_kind(id) {
+            let action = if enabled { "showing" } else { "hiding" };
+            log::info!("{action} the {kind} layer");
+        }
+    }
+    async fn new(window: Window, args: &Args) -> Self {
         let window = Arc::new(window);
 
         let size = window.inner_size();
@@ -252,7 +321,9 @@ impl WgpuEnv {
             desired_maximum_frame_latency: 2,
         };
 
-        let renderer = Renderer::new(&device, &queue, &config);
+        let mut renderer = Renderer::new(&device, &queue, &config);
+        args.configure(&mut renderer, &device, &queue);
+        args.watch(&mut renderer);
 
         Self {
             renderer,
@@ -0,0 +1,269 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use crate::render::{Backdrop, GradientStop, LayerKind, Renderer, WallpaperFit};
+
+/// Command line options
+#[derive(Debug)]
+pub struct Args {
+    /// Image to use instead of the built-in wallpaper
+    pub wallpaper: Option<PathBuf>,
+    pub wallpaper_fit: WallpaperFit,
+    /// Stop animated wallpapers on their last frame instead of looping
+    pub play_once: bool,
+    /// Procedural backdrop to show instead of the built-in wallpaper
+    pub backdrop: Option<BackdropPreset>,
+    /// Scene file to show instead of the demo scene, reloaded whenever it's saved
+    pub scene: Option<PathBuf>,
+    /// Layers to draw, bottom to top, instead of the wallpaper under the scene's shapes
+    pub layers: Option<Vec<LayerKind>>,
+    /// Render a single frame to this PNG without opening a window
+    pub screenshot: Option<PathBuf>,
+    /// Size of the screenshot in pixels
+    pub size: [u32; 2],
+    /// Render screenshots on a software adapter
+    pub software: bool,
+    /// Recompile the shaders from source whenever one is saved
+    pub watch_shaders: bool,
+}
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            wallpaper: None,
+            wallpaper_fit: WallpaperFit::default(),
+            play_once: false,
+            backdrop: None,
+            scene: None,
+            layers: None,
+            screenshot: None,
+            size: [800, 600],
+            software: false,
+            watch_shaders: false,
+        }
+    }
+}
+impl Args {
+    pub const USAGE: &str = "usage: effect-compositor-proto [--wallpaper <path>] \
+        [--wallpaper-fit cover|contain|stretch|tile|center] [--play-once] \
+        [--backdrop solid|linear-gradient|radial-gradient|noise] [--scene <path.ron>] \
+        [--layers wallpaper,glass,raymarching,shapes,...] [--watch-shaders] \
+        [--screenshot <path.png> [--size <width>x<height>] [--software]]";
+
+    pub fn parse() -> Result<Self, ArgsError> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+    fn parse_from(mut args: impl Iterator<Item = String>) -> Result<Self, ArgsError> {
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_owned(), Some(value.to_owned())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ArgsError::MissingValue(name.clone()))
+            };
+            match name.as_str() {
+                "--wallpaper" => parsed.wallpaper = Some(value()?.into()),
+                "--wallpaper-fit" => {
+                    parsed.wallpaper_fit = value()?.parse().map_err(ArgsError::Invalid)?;
+                }
+                "--play-once" => parsed.play_once = true,
+                "--backdrop" => {
+                    parsed.backdrop = Some(value()?.parse().map_err(ArgsError::Invalid)?);
+                }
+                "--scene" => parsed.scene = Some(value()?.into()),
+                "--layers" => {
+                    let layers = value()?
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<Result<_, _>>();
+                    parsed.layers = Some(layers.map_err(ArgsError::Invalid)?);
+                }
+                "--screenshot" => parsed.screenshot = Some(value()?.into()),
+                "--size" => parsed.size = parse_size(&value()?).map_err(ArgsError::Invalid)?,
+                "--software" => parsed.software = true,
+                "--watch-shaders" => parsed.watch_shaders = true,
+                _ => return Err(ArgsError::Unknown(name)),
+            }
+        }
+        Ok(parsed)
+    }
+    /// Applies the layer, scene and wallpaper options to a freshly created renderer.
+    /// Backdrops given on the command line take precedence over the scene's, also when it's
+    /// reloaded.
+    pub fn configure(&self, renderer: &mut Renderer, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Some(layers) = &self.layers {
+            renderer.arrange_layers(device, queue, layers);
+        }
+        renderer.set_wallpaper_fit(queue, self.wallpaper_fit);
+        renderer.set_wallpaper_looping(!self.play_once);
+        if let Some(path) = &self.scene
+            && let Err(err) = renderer.load_scene(device, queue, path)
+        {
+            log::error!("{err}, keeping the demo scene");
+        }
+        if let Some(preset) = self.backdrop {
+            renderer.set_backdrop(device, queue, preset.backdrop());
+            renderer.keep_backdrop();
+        }
+        if let Some(path) = &self.wallpaper {
+            match renderer.load_wallpaper(device, queue, path) {
+                Ok(()) => renderer.keep_backdrop(),
+                Err(err) => log::error!("{err}, keeping the built-in wallpaper"),
+            }
+        }
+    }
+    /// Starts reloading the scene file, and the shaders with `--watch-shaders`, when they
+    /// change on disk. Only the interactive window does this, screenshots render once.
+    pub fn watch(&self, renderer: &mut Renderer) {
+        if let Some(path) = &self.scene {
+            renderer.watch_scene(path);
+        }
+        if self.watch_shaders {
+            renderer.watch_shaders();
+        }
+    }
+}
+
+fn parse_size(s: &str) -> Result<[u32; 2], String> {
+    let invalid = || format!("invalid size {s}, expected <width>x<height>");
+    let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+    let size = [width, height].map(|d| d.parse::<u32>().ok().filter(|&d| d > 0));
+    match size {
+        [Some(width), Some(height)] => Ok([width, height]),
+        _ => Err(invalid()),
+    }
+}
+
+/// Built-in procedural backdrops selectable with `--backdrop`
+#[derive(Copy, Clone, Debug)]
+pub enum BackdropPreset {
+    Solid,
+    LinearGradient,
+    RadialGradient,
+    Noise,
+}
+impl BackdropPreset {
+    pub fn backdrop(self) -> Backdrop {
+        let stop = |offset, color: u32| GradientStop {
+            offset,
+            color: color.into(),
+        };
+        match self {
+            Self::Solid => Backdrop::Solid(0x3a4f6bff.into()),
+            Self::LinearGradient => Backdrop::LinearGradient {
+                start: [0.0, 0.0],
+                end: [1.0, 1.0],
+                stops: vec![
+                    stop(0.0, 0xff7e5fff),
+                    stop(0.5, 0xfeb47bff),
+                    stop(1.0, 0x6a82fbff),
+                ],
+            },
+            Self::RadialGradient => Backdrop::RadialGradient {
+                center: [0.5, 0.5],
+                radius: 0.75,
+                stops: vec![
+                    stop(0.0, 0xf9f871ff),
+                    stop(0.4, 0xff6f91ff),
+                    stop(1.0, 0x2c3e50ff),
+                ],
+            },
+            Self::Noise => Backdrop::Noise {
+                colors: [0x0f2027ff.into(), 0x7fdbdaff.into()],
+                scale: 6.0,
+                speed: 0.2,
+            },
+        }
+    }
+}
+impl FromStr for BackdropPreset {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solid" => Ok(Self::Solid),
+            "linear-gradient" => Ok(Self::LinearGradient),
+            "radial-gradient" => Ok(Self::RadialGradient),
+            "noise" => Ok(Self::Noise),
+            _ => Err(format!(
+                "unknown backdrop {s}, expected solid, linear-gradient, radial-gradient or noise"
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ArgsError {
+    Unknown(String),
+    MissingValue(String),
+    Invalid(String),
+}
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(arg) => write!(f, "unknown argument {arg}"),
+            Self::MissingValue(arg) => write!(f, "{arg} needs a value"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+impl std::error::Error for ArgsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse_from(args.iter().map(|&arg| arg.to_owned()))
+    }
+
+    #[test]
+    fn values_can_follow_or_be_inline() {
+        let args = parse(&["--scene", "a.ron", "--size=640x480", "--software"]).unwrap();
+        assert_eq!(args.scene, Some("a.ron".into()));
+        assert_eq!(args.size, [640, 480]);
+        assert!(args.software);
+        assert!(!args.play_once);
+    }
+
+    #[test]
+    fn layers_are_listed_bottom_to_top() {
+        let args = parse(&["--layers", "shapes,wallpaper,shapes"]).unwrap();
+        assert_eq!(
+            args.layers,
+            Some(vec![
+                LayerKind::Shapes,
+                LayerKind::Wallpaper,
+                LayerKind::Shapes
+            ])
+        );
+        assert!(matches!(
+            parse(&["--layers", "wallpaper,"]),
+            Err(ArgsError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn sizes_need_two_positive_dimensions() {
+        assert_eq!(parse_size("1x2"), Ok([1, 2]));
+        for size in ["0x2", "12", "x", "1x2x3", "-1x2"] {
+            assert!(parse_size(size).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn unknown_arguments_and_missing_values_are_errors() {
+        assert!(
+            matches!(parse(&["--wallpapr"]), Err(ArgsError::Unknown(arg)) if arg == "--wallpapr")
+        );
+        assert!(
+            matches!(parse(&["--wallpaper"]), Err(ArgsError::MissingValue(arg)) if arg == "--wallpaper")
+        );
+        assert!(matches!(
+            parse(&["--backdrop", "plaid"]),
+            Err(ArgsError::Invalid(_))
+        ));
+    }
+}
@@ -5,6 +5,7 @@ macro_rules! EnumShaderConstants {
         )*
         $vis:vis enum $Enum:ident {
             $(
+                $(#[$variant_attr:meta])*
                 $Variant:ident $(= $value:tt)?,
             )*
         }
@@ -12,10 +13,11 @@ macro_rules! EnumShaderConstants {
         $(
             #[$($attr)*]
         )*
-        #[derive(Copy, Clone, Debug, bytemuck::NoUninit, bytemuck::CheckedBitPattern)]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, bytemuck::NoUninit, bytemuck::CheckedBitPattern)]
         #[repr(u32)]
         $vis enum $Enum {
             $(
+                $(#[$variant_attr])*
                 $Variant $(= $value)?,
             )*
         }
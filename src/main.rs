@@ -1,15 +1,34 @@
 use app::App;
+use args::Args;
 use winit::event_loop::EventLoop;
 
 #[macro_use]
 mod macros;
 mod app;
+mod args;
 mod render;
+mod screenshot;
 
 fn main() {
     env_logger::init();
 
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{}", Args::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(path) = args.screenshot.clone() {
+        if let Err(err) = screenshot::run(&args, path) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let event_loop = EventLoop::new().unwrap();
-    let mut app = App::default();
+    let mut app = App::new(args);
     event_loop.run_app(&mut app).unwrap();
 }
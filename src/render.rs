@@ -1,71 +1,354 @@
+use std::path::{Path, PathBuf};
+
 use glass::{Glass, layer::GlassLayer};
+use layer::{Layer, LayerContext, LayerStack};
+pub use layer::{LayerId, LayerKind};
+pub use offscreen::{Offscreen, OffscreenError, headless_device};
 use raymarching::Raymarching;
+pub use scene::{Scene, SceneBackdrop, SceneError};
+use shader::{Shader, ShaderWatcher};
 use system::SystemGroup;
-use wallpaper::Wallpaper;
+pub use wallpaper::{Backdrop, BackdropColorSpace, GradientStop, WallpaperFit};
+use wallpaper::{Wallpaper, WallpaperError, WallpaperLayer};
+use watch::Watcher;
 
 mod glass;
+#[cfg(test)]
+mod golden;
+mod layer;
+mod offscreen;
 mod pointer;
 mod raymarching;
+mod scene;
+mod shader;
 mod shape;
 mod system;
 mod wallpaper;
+mod watch;
 
 pub struct Renderer {
     system: SystemGroup,
+    /// The backdrop every glass layer refracts, drawn by the wallpaper layer
     wallpaper: Wallpaper,
-    // glass: Glass,
-    // raymarching: Raymarching,
-    glass_layer: GlassLayer,
+    layers: LayerStack,
+    /// The current surface, for creating layers after startup
+    config: wgpu::SurfaceConfiguration,
+    /// The backdrop of the last loaded scene, saved back with the shapes while it's showing
+    scene_backdrop: Option<SceneBackdrop>,
+    /// Scenes leave the backdrop alone, see [`Renderer::keep_backdrop`]
+    backdrop_kept: bool,
+    scene_watcher: Option<Watcher>,
+    shader_watcher: Option<ShaderWatcher>,
 }
 impl Renderer {
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Some(watcher) = &mut self.scene_watcher
+            && watcher.poll()
+        {
+            let path = watcher.path().to_owned();
+            match self.load_scene(device, queue, &path) {
+                Ok(()) => log::info!("reloaded {}", path.display()),
+                Err(err) => log::error!("{err}, keeping the previous scene"),
+            }
+        }
+        if let Some(watcher) = &mut self.shader_watcher {
+            for (shader, source) in watcher.poll() {
+                log::info!("reloading the {shader:?} shader");
+                if shader == Shader::Wallpaper {
+                    self.wallpaper.reload_shader(device, &source);
+                }
+                let context = LayerContext {
+                    device,
+                    queue,
+                    system: &self.system,
+                    wallpaper: &self.wallpaper,
+                };
+                self.layers.reload_shader(&context, shader, &source);
+            }
+        }
+        self.wallpaper.prepare(device, queue);
+        let context = LayerContext {
+            device,
+            queue,
+            system: &self.system,
+            wallpaper: &self.wallpaper,
+        };
+        self.layers.prepare(&context);
+    }
     pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
-        self.wallpaper.draw(render_pass, &self.system);
-        // self.glass.draw(render_pass, &self.system, &self.wallpaper);
-        // self.raymarching
-        //     .draw(render_pass, &self.system, &self.wallpaper);
-        self.glass_layer
-            .draw(render_pass, &self.system, &self.wallpaper);
+        self.layers.draw(render_pass, &self.system, &self.wallpaper);
+    }
+    /// Creates a layer on top of the stack
+    pub fn add_layer(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        kind: LayerKind,
+    ) -> LayerId {
+        let context = LayerContext {
+            device,
+            queue,
+            system: &self.system,
+            wallpaper: &self.wallpaper,
+        };
+        self.layers
+            .push(kind, create_layer(&context, &self.config, kind))
+    }
+    pub fn remove_layer(&mut self, id: LayerId) -> bool {
+        self.layers.remove(id)
+    }
+    /// Moves a layer to `index` in the stack, counting from the bottom
+    pub fn move_layer(&mut self, id: LayerId, index: usize) {
+        self.layers.move_to(id, index);
+    }
+    /// Restacks the layers as `kinds`, bottom to top. Existing layers are kept for the
+    /// first entries of their kind, so the scene stays on the first shapes layer, and
+    /// the rest are created. Layers that aren't listed are removed.
+    pub fn arrange_layers(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        kinds: &[LayerKind],
+    ) {
+        let context = LayerContext {
+            device,
+            queue,
+            system: &self.system,
+            wallpaper: &self.wallpaper,
+        };
+        self.layers
+            .arrange(kinds, |kind| create_layer(&context, &self.config, kind));
+    }
+    /// The layers from bottom to top
+    pub fn layers(&self) -> impl Iterator<Item = LayerId> + '_ {
+        self.layers.ids()
+    }
+    pub fn layer_kind(&self, id: LayerId) -> Option<LayerKind> {
+        self.layers.kind(id)
+    }
+    pub fn is_layer_enabled(&self, id: LayerId) -> bool {
+        self.layers.is_enabled(id)
+    }
+    /// Hides or shows a layer. Hidden layers keep their state but aren't drawn and ignore
+    /// the pointer.
+    pub fn set_layer_enabled(&mut self, id: LayerId, enabled: bool) {
+        self.layers.set_enabled(id, enabled);
+    }
+    /// Swaps the wallpaper for the image at `path`, keeping the current one on error
+    pub fn load_wallpaper(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<(), WallpaperError> {
+        self.wallpaper.load(device, queue, path)?;
+        self.scene_backdrop = None;
+        Ok(())
+    }
+    /// Shows a procedural backdrop, or an image, behind the glass
+    pub fn set_backdrop(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, backdrop: Backdrop) {
+        self.wallpaper.set_backdrop(device, queue, backdrop);
+        self.scene_backdrop = None;
+    }
+    /// Puts the glass over a snapshot of any texture, such as UI rendered by the caller, in
+    /// place of the wallpaper. The texture must have `TextureUsages::TEXTURE_BINDING`. Its
+    /// content is copied now and the glass only sees the copy, so call
+    /// [`Renderer::refresh_backdrop_snapshot`] after each frame drawn into it.
+    pub fn set_backdrop_snapshot(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view: wgpu::TextureView,
+        size: [u32; 2],
+        color_space: BackdropColorSpace,
+    ) {
+        self.set_backdrop(
+            device,
+            queue,
+            Backdrop::TextureSnapshot {
+                view,
+                size,
+                color_space,
+            },
+        );
+    }
+    /// Copies the snapshot's texture again after the caller has submitted new drawing into
+    /// it, rebuilding the mips and blur the glass refracts
+    pub fn refresh_backdrop_snapshot(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.wallpaper.refresh_backdrop_snapshot(device, queue);
+    }
+    /// Replaces the shapes on the first shapes layer, and the backdrop if the scene has one
+    /// and it isn't kept, with the scene file at `path`. Loading the same file again only
+    /// applies what changed. Nothing changes on error.
+    pub fn load_scene(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        let scene = Scene::read(path)?;
+        if let Some(backdrop) = &scene.backdrop
+            && !self.backdrop_kept
+            && scene.backdrop != self.scene_backdrop
+        {
+            match backdrop {
+                SceneBackdrop::Wallpaper {
+                    path: wallpaper,
+                    fit,
+                } => {
+                    let wallpaper = path.parent().unwrap_or(Path::new("")).join(wallpaper);
+                    self.load_wallpaper(device, queue, wallpaper)
+                        .map_err(SceneError::Wallpaper)?;
+                    self.set_wallpaper_fit(queue, *fit);
+                }
+                backdrop => {
+                    let procedural = backdrop.procedural().expect("wallpapers are handled above");
+                    self.set_backdrop(device, queue, procedural);
+                }
+            }
+            self.scene_backdrop = scene.backdrop.clone();
+        }
+        if let Some(scene_layer) = self.layers.first_mut::<GlassLayer>() {
+            scene_layer.load_scene(&scene);
+        }
+        Ok(())
+    }
+    /// Makes scenes, including ones reloaded later, leave the current backdrop alone, so a
+    /// backdrop chosen on the command line wins over the scene file's
+    pub fn keep_backdrop(&mut self) {
+        self.backdrop_kept = true;
+    }
+    /// Development mode: compiles the shaders in this checkout's `src/shaders` whenever
+    /// one is saved and rebuilds the pipelines they changed
+    pub fn watch_shaders(&mut self) {
+        self.shader_watcher = Some(ShaderWatcher::new(ShaderWatcher::SOURCE_DIR));
+    }
+    /// Reloads the scene file at `path` whenever it's saved, logging errors instead of
+    /// returning them so a half-written file doesn't interrupt anything
+    pub fn watch_scene(&mut self, path: impl Into<PathBuf>) {
+        self.scene_watcher = Some(Watcher::new(path));
+    }
+    /// Writes the shapes on the first shapes layer as they are now, with the last loaded
+    /// scene's backdrop if it's still showing
+    pub fn save_scene(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        let scene = Scene {
+            backdrop: self.scene_backdrop.clone(),
+            ..self
+                .layers
+                .first::<GlassLayer>()
+                .map(GlassLayer::save_scene)
+                .unwrap_or_default()
+        };
+        scene.write(path)
+    }
+    /// Pauses an animated wallpaper if it's playing, resumes it otherwise
+    pub fn toggle_wallpaper_playback(&mut self) {
+        if self.wallpaper.is_playing() {
+            self.wallpaper.pause();
+        } else {
+            self.wallpaper.play();
+        }
+    }
+    pub fn set_wallpaper_looping(&mut self, looping: bool) {
+        self.wallpaper.set_looping(looping);
+    }
+    pub fn set_wallpaper_fit(&self, queue: &wgpu::Queue, fit: WallpaperFit) {
+        self.wallpaper.set_fit(queue, fit);
     }
     pub fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32) {
         self.system.resize(queue, width, height);
-        self.glass_layer
-            .resize(device, queue, &self.system, [width, height]);
+        self.wallpaper.resize(device, queue, [width, height]);
+        self.config.width = width;
+        self.config.height = height;
+        let context = LayerContext {
+            device,
+            queue,
+            system: &self.system,
+            wallpaper: &self.wallpaper,
+        };
+        self.layers.resize(&context, [width, height]);
     }
     pub fn cursor_move(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, x: f64, y: f64) {
         self.system.cursor_move(queue, x, y);
-        self.glass_layer
-            .cursor_move(device, queue, &self.system, &self.wallpaper, x, y);
+        let context = LayerContext {
+            device,
+            queue,
+            system: &self.system,
+            wallpaper: &self.wallpaper,
+        };
+        self.layers.cursor_move(&context, x, y);
     }
     pub fn cursor_leave(&self, queue: &wgpu::Queue) {
         self.system.cursor_leave(queue);
     }
     pub fn mouse_press(&mut self, queue: &wgpu::Queue) {
-        self.glass_layer.mouse_press(queue);
+        self.layers.mouse_press(queue);
     }
     pub fn mouse_release(&mut self, queue: &wgpu::Queue) {
-        self.glass_layer.mouse_release(queue);
+        self.layers.mouse_release(queue);
     }
     pub fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         config: &wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let demo =
+            Scene::parse(include_str!("../scenes/demo.ron")).expect("the demo scene is valid");
+        Self::with_scene(device, queue, config, |glass_layer| {
+            glass_layer.load_scene(&demo)
+        })
+    }
+    /// Builds the renderer with the wallpaper and a shapes layer holding the shapes added
+    /// by `scene`, instead of the demo scene
+    fn with_scene(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+        scene: impl FnOnce(&mut GlassLayer),
     ) -> Self {
         let system = SystemGroup::new(device, config);
         let wallpaper = Wallpaper::new(device, queue, config, &system);
-        // let glass = Glass::new(device, config, &system, &wallpaper);
-        // let raymarching = Raymarching::new(device, config, &system, &wallpaper);
         let mut glass_layer = GlassLayer::new(device, config, &system, &wallpaper);
-
-        glass_layer.insert_circle([128.0; 2], 48.0, 0x3399FFFF.into());
-        glass_layer.insert_circle([256.0, 128.0], 48.0, 0xFF4444FF.into());
+        scene(&mut glass_layer);
         glass_layer.init_gpu(device, queue, &system);
 
+        let mut layers = LayerStack::default();
+        layers.push(LayerKind::Wallpaper, Box::new(WallpaperLayer));
+        layers.push(LayerKind::Shapes, Box::new(glass_layer));
+
         Self {
             system,
             wallpaper,
-            // glass,
-            // raymarching,
-            glass_layer,
+            layers,
+            config: config.clone(),
+            scene_backdrop: None,
+            backdrop_kept: false,
+            scene_watcher: None,
+            shader_watcher: None,
+        }
+    }
+}
+
+fn create_layer(
+    context: &LayerContext,
+    config: &wgpu::SurfaceConfiguration,
+    kind: LayerKind,
+) -> Box<dyn Layer> {
+    let LayerContext {
+        device,
+        queue,
+        system,
+        wallpaper,
+    } = *context;
+    match kind {
+        LayerKind::Wallpaper => Box::new(WallpaperLayer),
+        LayerKind::Glass => Box::new(Glass::new(device, config, system, wallpaper)),
+        LayerKind::Raymarching => Box::new(Raymarching::new(device, config, system, wallpaper)),
+        LayerKind::Shapes => {
+            let mut layer = GlassLayer::new(device, config, system, wallpaper);
+            layer.init_gpu(device, queue, system);
+            Box::new(layer)
         }
     }
 }
@@ -3,7 +3,11 @@ use std::mem;
 use wesl::include_wesl;
 use wgpu::util::DeviceExt;
 
-use super::{system::SystemGroup, wallpaper::Wallpaper};
+use super::{
+    layer::{Layer, LayerContext},
+    system::SystemGroup,
+    wallpaper::Wallpaper,
+};
 
 pub mod layer;
 
@@ -13,8 +17,8 @@ pub struct Glass {
     pipeline: wgpu::RenderPipeline,
     state: GlassState,
 }
-impl Glass {
-    pub fn draw(
+impl Layer for Glass {
+    fn draw(
         &self,
         render_pass: &mut wgpu::RenderPass,
         system: &SystemGroup,
@@ -26,7 +30,7 @@ impl Glass {
         render_pass.set_vertex_buffer(0, self.instances.slice(..));
         render_pass.draw(0..6, 0..self.boxes.len() as _);
     }
-    pub fn cursor_move(&mut self, queue: &wgpu::Queue, x: f64, y: f64) {
+    fn cursor_move(&mut self, context: &LayerContext, x: f64, y: f64) {
         let next_state = match self.state.take() {
             GlassState::Idle => {
                 let hit = self.find_hovered_glass(x, y);
@@ -68,8 +72,9 @@ impl Glass {
                 let new_box_pos = [x as f32 - press_x, y as f32 - press_y].map(|d| d.round());
 
                 self.boxes[index].position = new_box_pos;
-                queue.write_buffer(&self.instances, 0, bytemuck::cast_slice(&self.boxes));
-                dbg!(new_box_pos);
+                context
+                    .queue
+                    .write_buffer(&self.instances, 0, bytemuck::cast_slice(&self.boxes));
                 GlassState::Dragging {
                     index,
                     press_position,
@@ -78,7 +83,10 @@ impl Glass {
         };
         self.state = next_state;
     }
-    pub fn mouse_press(&mut self, queue: &wgpu::Queue) {
+    fn is_hovered(&self) -> bool {
+        matches!(self.state, GlassState::Hovered { .. })
+    }
+    fn mouse_press(&mut self, queue: &wgpu::Queue) {
         let next_state = match self.state.take() {
             GlassState::Idle => GlassState::Idle,
             GlassState::Hovered {
@@ -88,24 +96,16 @@ impl Glass {
                 index,
                 press_position: hover_position,
             },
-            GlassState::Pressed {
-                index,
-                press_position,
-            } => todo!(),
-            GlassState::Dragging {
-                index,
-                press_position,
-            } => todo!(),
+            // Another button pressed mid-drag keeps the drag going
+            state @ (GlassState::Pressed { .. } | GlassState::Dragging { .. }) => state,
         };
         self.state = next_state;
     }
-    pub fn mouse_release(&mut self, queue: &wgpu::Queue) {
+    fn mouse_release(&mut self, queue: &wgpu::Queue) {
         let next_state = match self.state.take() {
             GlassState::Idle => GlassState::Idle,
-            GlassState::Hovered {
-                index,
-                hover_position,
-            } => todo!(),
+            // A release whose press went to another layer
+            state @ GlassState::Hovered { .. } => state,
             GlassState::Pressed {
                 index,
                 press_position,
@@ -123,6 +123,8 @@ impl Glass {
         };
         self.state = next_state;
     }
+}
+impl Glass {
     pub fn find_hovered_glass(&self, x: f64, y: f64) -> Option<GlassHit> {
         self.boxes.iter().enumerate().find_map(|(i, b)| {
             Some(GlassHit {
@@ -3,9 +3,13 @@ use wesl::include_wesl;
 use crate::{
     macros::EnumShaderConstants,
     render::{
+        layer::{Layer, LayerContext},
+        pointer::PickMode,
+        scene::Scene,
+        shader::{self, Shader},
         shape::{
             Shapes,
-            component::{RGBA, ShapeKind, vec2f},
+            component::{Material, RGBA, ShapeId, ShapeKind, vec2f},
         },
         system::SystemGroup,
         wallpaper::Wallpaper,
@@ -14,40 +18,106 @@ use crate::{
 
 pub struct GlassLayer {
     shapes: Shapes,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
     pipeline: wgpu::RenderPipeline,
 }
 impl GlassLayer {
-    pub fn insert_circle(&mut self, center: vec2f, radius: f32, tint_color: RGBA) {
-        self.shapes.insert_circle(center, radius, tint_color);
+    pub fn insert_circle(&mut self, center: vec2f, radius: f32, tint_color: RGBA) -> ShapeId {
+        self.shapes.insert_circle(center, radius, tint_color)
     }
-}
-impl GlassLayer {
-    pub fn cursor_move(
+    pub fn insert_rounded_rect(
         &mut self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        system: &SystemGroup,
-        wallpaper: &Wallpaper,
-        x: f64,
-        y: f64,
-    ) {
+        center: vec2f,
+        half_size: vec2f,
+        radius: f32,
+        tint_color: RGBA,
+    ) -> ShapeId {
         self.shapes
-            .cursor_move(device, queue, system, wallpaper, x, y);
+            .insert_rounded_rect(center, half_size, radius, tint_color)
     }
-    pub fn mouse_press(&mut self, queue: &wgpu::Queue) {
-        self.shapes.mouse_press(queue);
+    pub fn insert_squircle(
+        &mut self,
+        center: vec2f,
+        half_size: vec2f,
+        exponent: f32,
+        tint_color: RGBA,
+    ) -> ShapeId {
+        self.shapes
+            .insert_squircle(center, half_size, exponent, tint_color)
     }
-    pub fn mouse_release(&mut self, queue: &wgpu::Queue) {
-        self.shapes.mouse_release(queue);
+    pub fn insert_superformula(
+        &mut self,
+        center: vec2f,
+        sides: f32,
+        radii: vec2f,
+        exponents: [f32; 3],
+        tint_color: RGBA,
+    ) -> ShapeId {
+        self.shapes
+            .insert_superformula(center, sides, radii, exponents, tint_color)
     }
 }
 impl GlassLayer {
-    pub fn draw(
+    /// Makes the shapes, pick mode and blend distance match the scene's, leaving the
+    /// backdrop to the caller. Shapes that only changed in place keep their ids and are updated, so
+    /// reloading an edited scene uploads just the difference.
+    pub fn load_scene(&mut self, scene: &Scene) {
+        self.shapes.update(&scene.shapes);
+        self.shapes.set_pick_mode(scene.pick_mode);
+        self.shapes.set_blend_distance(scene.blend_distance);
+    }
+    /// Captures the shapes as they are now, including any the pointer has dragged.
+    /// The backdrop is left empty.
+    pub fn save_scene(&self) -> Scene {
+        Scene {
+            backdrop: None,
+            pick_mode: self.shapes.pick_mode(),
+            blend_distance: self.shapes.blend_distance(),
+            shapes: self.shapes.scene_shapes(),
+        }
+    }
+    pub fn remove(&mut self, id: ShapeId) -> bool {
+        self.shapes.remove(id)
+    }
+    pub fn set_pick_mode(&mut self, pick_mode: PickMode) {
+        self.shapes.set_pick_mode(pick_mode);
+    }
+    pub fn set_center(&mut self, id: ShapeId, center: vec2f) {
+        self.shapes.set_center(id, center);
+    }
+    /// Rotates the shape clockwise about its center, in radians
+    pub fn set_rotation(&mut self, id: ShapeId, rotation: f32) {
+        self.shapes.set_rotation(id, rotation);
+    }
+    pub fn set_scale(&mut self, id: ShapeId, scale: vec2f) {
+        self.shapes.set_scale(id, scale);
+    }
+    pub fn set_material(&mut self, id: ShapeId, material: Material) {
+        self.shapes.set_material(id, material);
+    }
+    pub fn raise(&mut self, id: ShapeId) {
+        self.shapes.raise(id);
+    }
+    pub fn lower(&mut self, id: ShapeId) {
+        self.shapes.lower(id);
+    }
+    pub fn bring_to_front(&mut self, id: ShapeId) {
+        self.shapes.bring_to_front(id);
+    }
+    pub fn send_to_back(&mut self, id: ShapeId) {
+        self.shapes.send_to_back(id);
+    }
+}
+impl Layer for GlassLayer {
+    fn draw(
         &self,
         render_pass: &mut wgpu::RenderPass,
         system: &SystemGroup,
         wallpaper: &Wallpaper,
     ) {
+        // Texture backdrops are snapshotted into the wallpaper too, so its bind group is
+        // always the one the glass refracts
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &system.bind_group, &[]);
         render_pass.set_bind_group(1, self.shapes.bind_group(), &[]);
@@ -56,15 +126,59 @@ impl GlassLayer {
         render_pass.set_bind_group(4, &wallpaper.texture.bind_group, &[]);
         render_pass.draw(0..6, 0..1);
     }
-    pub fn resize(
-        &mut self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        system: &SystemGroup,
-        size: [u32; 2],
-    ) {
-        self.shapes.resize(device, queue, system, size);
+    fn resize(&mut self, context: &LayerContext, size: [u32; 2]) {
+        self.shapes
+            .resize(context.device, context.queue, context.system, size);
+    }
+    fn cursor_move(&mut self, context: &LayerContext, x: f64, y: f64) {
+        self.shapes.cursor_move(
+            context.device,
+            context.queue,
+            context.system,
+            context.wallpaper,
+            x,
+            y,
+        );
+    }
+    fn is_hovered(&self) -> bool {
+        self.shapes.is_hovered()
+    }
+    fn mouse_press(&mut self, queue: &wgpu::Queue) {
+        self.shapes.mouse_press(queue);
     }
+    fn mouse_release(&mut self, queue: &wgpu::Queue) {
+        self.shapes.mouse_release(queue);
+    }
+    fn prepare(&mut self, context: &LayerContext) {
+        self.shapes
+            .prepare(context.device, context.queue, context.system);
+    }
+    /// Rebuilds the layer's own pipeline, or the silhouette or light maps pipeline and
+    /// regenerates its output
+    fn reload_shader(&mut self, context: &LayerContext, shader: Shader, source: &str) {
+        let device = context.device;
+        match shader {
+            Shader::GlassShapes => {
+                if let Some(pipeline) = shader::try_create_pipeline(
+                    device,
+                    "glass shapes layer shader",
+                    source,
+                    |shader| {
+                        Self::create_pipeline(device, &self.pipeline_layout, self.format, shader)
+                    },
+                ) {
+                    self.pipeline = pipeline;
+                }
+            }
+            Shader::SilhouetteSdf | Shader::LightMaps => {
+                self.shapes
+                    .reload_shader(device, context.queue, context.system, shader, source);
+            }
+            Shader::Wallpaper => {}
+        }
+    }
+}
+impl GlassLayer {
     pub fn init_gpu(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, system: &SystemGroup) {
         self.shapes.init_gpu(device, queue, system);
     }
@@ -93,25 +207,38 @@ impl GlassLayer {
             push_constant_ranges: &[],
         });
 
-        let shape_kinds = ShapeKind::SHADER_CONSTANTS;
+        let pipeline = Self::create_pipeline(device, &pipeline_layout, config.format, &shader);
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        Self {
+            shapes,
+            pipeline_layout,
+            format: config.format,
+            pipeline,
+        }
+    }
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        shader: &wgpu::ShaderModule,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("glass shapes layer render pipeline"),
-            layout: Some(&pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 buffers: &[],
                 compilation_options: wgpu::PipelineCompilationOptions {
-                    constants: shape_kinds,
+                    constants: ShapeKind::SHADER_CONSTANTS,
                     ..Default::default()
                 },
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -137,8 +264,6 @@ impl GlassLayer {
             },
             multiview: None,
             cache: None,
-        });
-
-        Self { shapes, pipeline }
+        })
     }
 }
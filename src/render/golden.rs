@@ -0,0 +1,226 @@
+//! Golden image tests: canonical scenes are rendered offscreen on a software adapter and
+//! compared against the reference PNGs in `tests/golden`, so shader changes can't silently
+//! change the look.
+//!
+//! They need an adapter, so they're ignored by default. Run them with
+//! `cargo test -- --ignored`. A missing reference fails the test. Set `GOLDEN_BLESS=1` to
+//! record the references, or overwrite them after an intended change. On a mismatch the
+//! actual frame and a diff image are written next to the reference.
+
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+
+use super::{
+    Offscreen, Renderer, glass::layer::GlassLayer, headless_device, shape::component::Material,
+};
+
+const SIZE: [u32; 2] = [256, 256];
+
+/// Per-pixel YIQ distance above which a pixel counts as different, as a fraction of the
+/// largest possible distance
+const PIXEL_THRESHOLD: f32 = 0.1;
+/// Fraction of differing pixels a frame may have before it fails, to absorb rasterizer
+/// and rounding differences between software adapters
+const MAX_DIFFERING: f32 = 0.001;
+
+#[test]
+#[ignore = "needs a GPU or software adapter, run with --ignored"]
+fn single_circle() {
+    check("single_circle", |layer| {
+        layer.insert_circle([128.0; 2], 64.0, 0x3399FFFF.into());
+    });
+}
+
+#[test]
+#[ignore = "needs a GPU or software adapter, run with --ignored"]
+fn merged_circles() {
+    check("merged_circles", |layer| {
+        layer.insert_circle([96.0, 128.0], 48.0, 0x3399FFFF.into());
+        layer.insert_circle([164.0, 128.0], 48.0, 0xFF4444FF.into());
+    });
+}
+
+#[test]
+#[ignore = "needs a GPU or software adapter, run with --ignored"]
+fn dragged_shape() {
+    check_with("dragged_shape", |renderer, device, queue| {
+        renderer
+            .layers
+            .first_mut::<GlassLayer>()
+            .expect("the renderer starts with a shapes layer")
+            .insert_circle([96.0; 2], 48.0, 0x3399FFFF.into());
+        renderer.prepare(device, queue);
+
+        renderer.cursor_move(device, queue, 96.0, 96.0);
+        renderer.mouse_press(queue);
+        // The first move after a press only starts the drag
+        renderer.cursor_move(device, queue, 96.0, 96.0);
+        renderer.cursor_move(device, queue, 160.0, 168.0);
+        renderer.mouse_release(queue);
+    });
+}
+
+#[test]
+#[ignore = "needs a GPU or software adapter, run with --ignored"]
+fn rounded_rect() {
+    check("rounded_rect", |layer| {
+        let card = layer.insert_rounded_rect([128.0; 2], [96.0, 56.0], 24.0, 0x44DD88FF.into());
+        layer.set_rotation(card, -0.2);
+        layer.set_material(
+            card,
+            Material {
+                blur_radius: 16.0,
+                ..Default::default()
+            },
+        );
+    });
+}
+
+#[test]
+#[ignore = "needs a GPU or software adapter, run with --ignored"]
+fn squircle() {
+    check("squircle", |layer| {
+        layer.insert_squircle([128.0; 2], [80.0, 80.0], 4.0, 0xFFCC33FF.into());
+    });
+}
+
+#[test]
+#[ignore = "needs a GPU or software adapter, run with --ignored"]
+fn superformula() {
+    check("superformula", |layer| {
+        let crystal =
+            layer.insert_superformula([128.0; 2], 5.0, [72.0; 2], [3.0; 3], 0xCC66FFFF.into());
+        layer.set_material(
+            crystal,
+            Material {
+                ior: 1.8,
+                thickness: 48.0,
+                dispersion: 0.15,
+                ..Default::default()
+            },
+        );
+    });
+}
+
+/// Renders the shapes added by `scene` and compares them against the reference `name`
+fn check(name: &str, scene: impl FnOnce(&mut GlassLayer)) {
+    check_scene(name, scene, |_, _, _| {});
+}
+
+/// Renders the frame left after `interact` has populated and driven the renderer, for
+/// scenes that depend on pointer input
+fn check_with(name: &str, interact: impl FnOnce(&mut Renderer, &wgpu::Device, &wgpu::Queue)) {
+    check_scene(name, |_| {}, interact);
+}
+
+fn check_scene(
+    name: &str,
+    scene: impl FnOnce(&mut GlassLayer),
+    interact: impl FnOnce(&mut Renderer, &wgpu::Device, &wgpu::Queue),
+) {
+    let (device, queue) = pollster::block_on(headless_device(true))
+        .unwrap_or_else(|err| panic!("golden image {name} needs a software adapter: {err}"));
+    let offscreen = Offscreen::new(&device, SIZE, wgpu::TextureFormat::Rgba8Unorm)
+        .expect("golden image size fits any adapter");
+    let mut renderer = Renderer::with_scene(&device, &queue, &offscreen.config(), scene);
+    interact(&mut renderer, &device, &queue);
+    let actual = offscreen
+        .render(&device, &queue, &mut renderer)
+        .unwrap_or_else(|err| panic!("rendering golden image {name} failed: {err}"));
+
+    let reference_path = golden_path(name, "png");
+    if std::env::var_os("GOLDEN_BLESS").is_some() {
+        save(&actual, &reference_path);
+        eprintln!("recorded golden image {}", reference_path.display());
+        return;
+    }
+    if !reference_path.exists() {
+        save(&actual, &golden_path(name, "actual.png"));
+        panic!(
+            "golden image {} is missing, run with GOLDEN_BLESS=1 to record it",
+            reference_path.display()
+        );
+    }
+    let reference = image::open(&reference_path)
+        .unwrap_or_else(|err| panic!("couldn't read {}: {err}", reference_path.display()))
+        .into_rgba8();
+
+    if reference.dimensions() != actual.dimensions() {
+        save(&actual, &golden_path(name, "actual.png"));
+        panic!(
+            "golden image {name} is {:?} but the frame is {:?}",
+            reference.dimensions(),
+            actual.dimensions()
+        );
+    }
+    let (differing, diff) = compare(&reference, &actual);
+    let total = (actual.width() * actual.height()) as f32;
+    if differing as f32 / total > MAX_DIFFERING {
+        let actual_path = golden_path(name, "actual.png");
+        let diff_path = golden_path(name, "diff.png");
+        save(&actual, &actual_path);
+        save(&diff, &diff_path);
+        panic!(
+            "golden image {name} differs in {differing} pixels, see {} and {}",
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+/// Counts the pixels that differ noticeably and draws them in red over a faded grayscale
+/// copy of the reference
+fn compare(reference: &RgbaImage, actual: &RgbaImage) -> (usize, RgbaImage) {
+    let mut differing = 0;
+    let diff = RgbaImage::from_fn(reference.width(), reference.height(), |x, y| {
+        let expected = reference.get_pixel(x, y);
+        if color_distance(expected, actual.get_pixel(x, y)) > PIXEL_THRESHOLD {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [y, ..] = yiq(expected);
+            let faded = (255.0 - 0.1 * (255.0 - y)) as u8;
+            Rgba([faded, faded, faded, 255])
+        }
+    });
+    (differing, diff)
+}
+
+/// Perceptual distance between two colors in YIQ space, weighted towards brightness as
+/// in pixelmatch, scaled to 0..=1
+fn color_distance(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    const MAX_DISTANCE: f32 = 35215.0;
+    let [ya, ia, qa] = yiq(a);
+    let [yb, ib, qb] = yiq(b);
+    let (y, i, q) = (ya - yb, ia - ib, qa - qb);
+    let distance = 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+    (distance / MAX_DISTANCE).sqrt()
+}
+
+/// Converts to YIQ after blending onto white, so transparent pixels compare by how they look
+fn yiq(&Rgba([r, g, b, a]): &Rgba<u8>) -> [f32; 3] {
+    let alpha = a as f32 / 255.0;
+    let [r, g, b] = [r, g, b].map(|c| 255.0 + (c as f32 - 255.0) * alpha);
+    [
+        0.298_895 * r + 0.586_622 * g + 0.114_482 * b,
+        0.595_978 * r - 0.274_176 * g - 0.321_802 * b,
+        0.211_470 * r - 0.522_617 * g + 0.311_147 * b,
+    ]
+}
+
+fn golden_path(name: &str, extension: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.{extension}"))
+}
+
+fn save(image: &RgbaImage, path: &Path) {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .unwrap_or_else(|err| panic!("couldn't create {}: {err}", dir.display()));
+    }
+    image
+        .save(path)
+        .unwrap_or_else(|err| panic!("couldn't write {}: {err}", path.display()));
+}
@@ -0,0 +1,303 @@
+//! Everything the renderer draws is a layer in an ordered stack, drawn bottom to top into
+//! the same render pass. Layers can be added, removed, hidden and reordered at runtime.
+
+use std::{any::Any, fmt, str::FromStr};
+
+use super::{shader::Shader, system::SystemGroup, wallpaper::Wallpaper};
+
+/// What layers share: the device, the viewport and cursor uniforms, and the wallpaper the
+/// glass refracts
+pub struct LayerContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub system: &'a SystemGroup,
+    pub wallpaper: &'a Wallpaper,
+}
+
+pub trait Layer: Any {
+    fn draw(&self, render_pass: &mut wgpu::RenderPass, system: &SystemGroup, wallpaper: &Wallpaper);
+    /// Called for hidden layers too, so they're the right size when shown again
+    fn resize(&mut self, _context: &LayerContext, _size: [u32; 2]) {}
+    fn cursor_move(&mut self, _context: &LayerContext, _x: f64, _y: f64) {}
+    /// Whether the pointer is over something in this layer, so a press goes to it rather
+    /// than to the layers below
+    fn is_hovered(&self) -> bool {
+        false
+    }
+    fn mouse_press(&mut self, _queue: &wgpu::Queue) {}
+    fn mouse_release(&mut self, _queue: &wgpu::Queue) {}
+    /// Uploads whatever changed since the last frame, called before drawing
+    fn prepare(&mut self, _context: &LayerContext) {}
+    /// Swaps in a pipeline built from a recompiled shader, if this layer uses it
+    fn reload_shader(&mut self, _context: &LayerContext, _shader: Shader, _source: &str) {}
+}
+
+/// The layers the renderer knows how to create
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayerKind {
+    /// The wallpaper or backdrop itself
+    Wallpaper,
+    /// The original instanced glass boxes
+    Glass,
+    /// The raymarched 3D glass scene
+    Raymarching,
+    /// Glass shapes. Scenes are loaded into the first one in the stack.
+    Shapes,
+}
+impl LayerKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Wallpaper => "wallpaper",
+            Self::Glass => "glass",
+            Self::Raymarching => "raymarching",
+            Self::Shapes => "shapes",
+        }
+    }
+}
+impl fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for LayerKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wallpaper" => Ok(Self::Wallpaper),
+            "glass" => Ok(Self::Glass),
+            "raymarching" => Ok(Self::Raymarching),
+            "shapes" => Ok(Self::Shapes),
+            _ => Err(format!(
+                "unknown layer {s}, expected wallpaper, glass, raymarching or shapes"
+            )),
+        }
+    }
+}
+
+/// Stays the same while the layer is reordered, hidden or shown
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LayerId(u32);
+
+struct LayerEntry {
+    id: LayerId,
+    kind: LayerKind,
+    enabled: bool,
+    layer: Box<dyn Layer>,
+}
+
+#[derive(Default)]
+pub struct LayerStack {
+    /// Bottom to top
+    entries: Vec<LayerEntry>,
+    next_id: u32,
+    /// The layer that took the last press, which gets the release even if another layer
+    /// is hovered by then
+    pressed: Option<LayerId>,
+}
+impl LayerStack {
+    /// Adds a layer on top
+    pub fn push(&mut self, kind: LayerKind, layer: Box<dyn Layer>) -> LayerId {
+        let id = LayerId(self.next_id);
+        self.next_id += 1;
+        self.entries.push(LayerEntry {
+            id,
+            kind,
+            enabled: true,
+            layer,
+        });
+        id
+    }
+    pub fn remove(&mut self, id: LayerId) -> bool {
+        let Some(position) = self.position(id) else {
+            return false;
+        };
+        self.entries.remove(position);
+        true
+    }
+    /// Moves a layer to `index` in the stack, counting from the bottom. Indices past the
+    /// top move it to the top.
+    pub fn move_to(&mut self, id: LayerId, index: usize) {
+        if let Some(position) = self.position(id) {
+            let entry = self.entries.remove(position);
+            let index = index.min(self.entries.len());
+            self.entries.insert(index, entry);
+        }
+    }
+    /// Restacks the layers as `kinds`, bottom to top, reusing existing layers of each kind
+    /// in order and calling `create` for the rest. Layers left over are removed.
+    pub fn arrange(
+        &mut self,
+        kinds: &[LayerKind],
+        mut create: impl FnMut(LayerKind) -> Box<dyn Layer>,
+    ) {
+        let mut existing = std::mem::take(&mut self.entries);
+        for &kind in kinds {
+            match existing.iter().position(|entry| entry.kind == kind) {
+                Some(position) => self.entries.push(existing.remove(position)),
+                None => {
+                    self.push(kind, create(kind));
+                }
+            }
+        }
+    }
+    /// Bottom to top
+    pub fn ids(&self) -> impl Iterator<Item = LayerId> + '_ {
+        self.entries.iter().map(|entry| entry.id)
+    }
+    pub fn kind(&self, id: LayerId) -> Option<LayerKind> {
+        Some(self.entry(id)?.kind)
+    }
+    pub fn is_enabled(&self, id: LayerId) -> bool {
+        self.entry(id).is_some_and(|entry| entry.enabled)
+    }
+    /// Hidden layers aren't drawn, prepared or sent pointer events
+    pub fn set_enabled(&mut self, id: LayerId, enabled: bool) {
+        if let Some(position) = self.position(id) {
+            self.entries[position].enabled = enabled;
+        }
+    }
+    /// The bottom-most layer of type `T`
+    pub fn first<T: Layer>(&self) -> Option<&T> {
+        self.entries
+            .iter()
+            .find_map(|entry| (entry.layer.as_ref() as &dyn Any).downcast_ref())
+    }
+    pub fn first_mut<T: Layer>(&mut self) -> Option<&mut T> {
+        self.entries
+            .iter_mut()
+            .find_map(|entry| (entry.layer.as_mut() as &mut dyn Any).downcast_mut())
+    }
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        system: &SystemGroup,
+        wallpaper: &Wallpaper,
+    ) {
+        for layer in self.enabled() {
+            layer.draw(render_pass, system, wallpaper);
+        }
+    }
+    pub fn resize(&mut self, context: &LayerContext, size: [u32; 2]) {
+        for entry in &mut self.entries {
+            entry.layer.resize(context, size);
+        }
+    }
+    pub fn cursor_move(&mut self, context: &LayerContext, x: f64, y: f64) {
+        for layer in self.enabled_mut() {
+            layer.cursor_move(context, x, y);
+        }
+    }
+    /// Only the topmost hovered layer is pressed, so overlapping layers don't all start
+    /// dragging
+    pub fn mouse_press(&mut self, queue: &wgpu::Queue) {
+        let Some(entry) = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.enabled && entry.layer.is_hovered())
+        else {
+            return;
+        };
+        entry.layer.mouse_press(queue);
+        self.pressed = Some(entry.id);
+    }
+    pub fn mouse_release(&mut self, queue: &wgpu::Queue) {
+        if let Some(id) = self.pressed.take()
+            && let Some(position) = self.position(id)
+        {
+            self.entries[position].layer.mouse_release(queue);
+        }
+    }
+    pub fn prepare(&mut self, context: &LayerContext) {
+        for layer in self.enabled_mut() {
+            layer.prepare(context);
+        }
+    }
+    /// Hidden layers are reloaded too, so they're current when shown again
+    pub fn reload_shader(&mut self, context: &LayerContext, shader: Shader, source: &str) {
+        for entry in &mut self.entries {
+            entry.layer.reload_shader(context, shader, source);
+        }
+    }
+    fn enabled(&self) -> impl Iterator<Item = &dyn Layer> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.layer.as_ref())
+    }
+    fn enabled_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Layer>> {
+        self.entries
+            .iter_mut()
+            .filter(|entry| entry.enabled)
+            .map(|entry| &mut entry.layer)
+    }
+    fn entry(&self, id: LayerId) -> Option<&LayerEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+    fn position(&self, id: LayerId) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Empty;
+    impl Layer for Empty {
+        fn draw(&self, _: &mut wgpu::RenderPass, _: &SystemGroup, _: &Wallpaper) {}
+    }
+
+    fn stack(kinds: &[LayerKind]) -> LayerStack {
+        let mut stack = LayerStack::default();
+        for &kind in kinds {
+            stack.push(kind, Box::new(Empty));
+        }
+        stack
+    }
+    fn kinds(stack: &LayerStack) -> Vec<LayerKind> {
+        stack.ids().filter_map(|id| stack.kind(id)).collect()
+    }
+
+    #[test]
+    fn move_to_clamps_past_the_top() {
+        use LayerKind::*;
+        let mut stack = stack(&[Wallpaper, Glass, Shapes]);
+        let ids: Vec<_> = stack.ids().collect();
+
+        stack.move_to(ids[0], 10);
+        assert_eq!(kinds(&stack), [Glass, Shapes, Wallpaper]);
+        stack.move_to(ids[2], 0);
+        assert_eq!(kinds(&stack), [Shapes, Glass, Wallpaper]);
+    }
+
+    #[test]
+    fn arrange_reuses_layers_and_drops_the_rest() {
+        use LayerKind::*;
+        let mut stack = stack(&[Wallpaper, Glass, Shapes]);
+        let ids: Vec<_> = stack.ids().collect();
+        stack.set_enabled(ids[2], false);
+
+        let mut created = Vec::new();
+        stack.arrange(&[Shapes, Raymarching, Wallpaper], |kind| {
+            created.push(kind);
+            Box::new(Empty)
+        });
+        assert_eq!(created, [Raymarching]);
+        assert_eq!(kinds(&stack), [Shapes, Raymarching, Wallpaper]);
+
+        let arranged: Vec<_> = stack.ids().collect();
+        assert_eq!((arranged[0], arranged[2]), (ids[2], ids[0]));
+        assert!(!stack.is_enabled(ids[2]));
+        assert_eq!(stack.kind(ids[1]), None);
+    }
+
+    #[test]
+    fn kinds_round_trip_through_their_names() {
+        use LayerKind::*;
+        for kind in [Wallpaper, Glass, Raymarching, Shapes] {
+            assert_eq!(kind.to_string().parse(), Ok(kind));
+        }
+        assert!("glas".parse::<LayerKind>().is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::{error::Error, fmt, sync::mpsc};
+
+use super::Renderer;
+
+/// A texture the renderer can draw into instead of a surface, plus a buffer to read it back
+/// from, so frames can be captured without a window
+pub struct Offscreen {
+    texture: wgpu::Texture,
+    readback_buffer: wgpu::Buffer,
+    /// Rows in the readback buffer are padded to `COPY_BYTES_PER_ROW_ALIGNMENT`
+    padded_bytes_per_row: u32,
+}
+impl Offscreen {
+    /// Stands in for the surface configuration the renderer's pipelines are built against
+    pub fn config(&self) -> wgpu::SurfaceConfiguration {
+        wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: self.texture.format(),
+            width: self.texture.width(),
+            height: self.texture.height(),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::PreMultiplied,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        }
+    }
+
+    /// Prepares and draws one frame, then waits for the GPU and copies it out
+    pub fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        renderer: &mut Renderer,
+    ) -> Result<image::RgbaImage, OffscreenError> {
+        renderer.prepare(device, queue);
+
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("offscreen render encoder"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("offscreen render pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            renderer.draw(&mut render_pass);
+        }
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(self.texture.height()),
+                },
+            },
+            self.texture.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.read_back(device)
+    }
+    fn read_back(&self, device: &wgpu::Device) -> Result<image::RgbaImage, OffscreenError> {
+        let (sender, receiver) = mpsc::channel();
+        let slice = self.readback_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::Wait)
+            .map_err(OffscreenError::Poll)?;
+        receiver
+            .recv()
+            .expect("map callback runs once the device is polled")
+            .map_err(OffscreenError::Map)?;
+
+        let (width, height) = (self.texture.width(), self.texture.height());
+        let row_bytes = 4 * width as usize;
+        let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+        {
+            let mapped = slice.get_mapped_range();
+            for row in mapped.chunks(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..row_bytes]);
+            }
+        }
+        self.readback_buffer.unmap();
+
+        if matches!(
+            self.texture.format(),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(image::RgbaImage::from_raw(width, height, pixels)
+            .expect("readback holds exactly width * height pixels"))
+    }
+
+    /// `format` must be one of the 8 bit RGBA or BGRA formats so frames map onto an
+    /// [`image::RgbaImage`]. The renderer's shaders encode their own output, so use a
+    /// non-sRGB format to match what ends up on screen.
+    pub fn new(
+        device: &wgpu::Device,
+        [width, height]: [u32; 2],
+        format: wgpu::TextureFormat,
+    ) -> Result<Self, OffscreenError> {
+        if !matches!(
+            format,
+            wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Rgba8UnormSrgb
+                | wgpu::TextureFormat::Bgra8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            return Err(OffscreenError::UnsupportedFormat(format));
+        }
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+            return Err(OffscreenError::InvalidSize {
+                size: [width, height],
+                max_dimension,
+            });
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            label: Some("offscreen texture"),
+            view_formats: &[],
+        });
+        let padded_bytes_per_row = (4 * width).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen readback buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            texture,
+            readback_buffer,
+            padded_bytes_per_row,
+        })
+    }
+}
+
+/// Creates a device without a surface, for rendering offscreen. With `force_fallback`
+/// this picks a software adapter such as llvmpipe or WARP where one is installed.
+pub async fn headless_device(
+    force_fallback: bool,
+) -> Result<(wgpu::Device, wgpu::Queue), OffscreenError> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: force_fallback,
+        })
+        .await
+        .map_err(OffscreenError::Adapter)?;
+    log::info!("rendering offscreen with {:?}", adapter.get_info());
+
+    adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            // Software adapters vary a lot, so take whatever they offer rather than failing
+            // on a fixed set of limits
+            required_limits: adapter.limits(),
+            label: Some("headless device"),
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        })
+        .await
+        .map_err(OffscreenError::Device)
+}
+
+#[derive(Debug)]
+pub enum OffscreenError {
+    Adapter(wgpu::RequestAdapterError),
+    Device(wgpu::RequestDeviceError),
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// Either side is zero or exceeds the device's `max_texture_dimension_2d`
+    InvalidSize {
+        size: [u32; 2],
+        max_dimension: u32,
+    },
+    Poll(wgpu::PollError),
+    Map(wgpu::BufferAsyncError),
+}
+impl fmt::Display for OffscreenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Adapter(err) => write!(f, "no suitable adapter: {err}"),
+            Self::Device(err) => write!(f, "couldn't create a device: {err}"),
+            Self::UnsupportedFormat(format) => write!(
+                f,
+                "can't read back {format:?}, expected an 8 bit RGBA or BGRA format"
+            ),
+            Self::InvalidSize {
+                size: [width, height],
+                max_dimension,
+            } => write!(
+                f,
+                "can't render {width}x{height} offscreen, sides must be between 1 and {max_dimension}"
+            ),
+            Self::Poll(err) => write!(f, "waiting for the frame failed: {err}"),
+            Self::Map(err) => write!(f, "couldn't read the frame back: {err}"),
+        }
+    }
+}
+impl Error for OffscreenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Adapter(err) => Some(err),
+            Self::Device(err) => Some(err),
+            Self::Poll(err) => Some(err),
+            Self::Map(err) => Some(err),
+            Self::UnsupportedFormat(_) | Self::InvalidSize { .. } => None,
+        }
+    }
+}
@@ -26,6 +26,19 @@ impl PointerState {
     pub fn take(&mut self) -> Self {
         mem::take(self)
     }
+    pub fn is_hovered(&self) -> bool {
+        matches!(self, Self::Hovered { .. })
+    }
+}
+
+/// How the pointer decides which shape it is over
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PickMode {
+    /// Inside an individual shape's outline
+    #[default]
+    Shape,
+    /// Inside the smooth-blended scene outline, including the bridges between merged shapes
+    Scene,
 }
 
 pub trait BoundingBox {
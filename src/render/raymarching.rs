@@ -3,20 +3,25 @@ use std::{array, mem};
 use wesl::include_wesl;
 use wgpu::util::DeviceExt;
 
+pub(super) use kind::ShapeKind;
+
 use super::{
+    layer::{Layer, LayerContext},
     pointer::{PointerHit, PointerState},
     system::SystemGroup,
     wallpaper::Wallpaper,
 };
 
+mod kind;
+
 pub struct Raymarching {
     shapes: Shapes,
     state: PointerState,
     shapes_buffers: ShapesBuffers,
     pipeline: wgpu::RenderPipeline,
 }
-impl Raymarching {
-    pub fn draw(
+impl Layer for Raymarching {
+    fn draw(
         &self,
         render_pass: &mut wgpu::RenderPass,
         system: &SystemGroup,
@@ -29,7 +34,7 @@ impl Raymarching {
         render_pass.draw(0..6, 0..1);
     }
 
-    pub fn cursor_move(&mut self, queue: &wgpu::Queue, x: f64, y: f64) {
+    fn cursor_move(&mut self, context: &LayerContext, x: f64, y: f64) {
         let next_state = match self.state.take() {
             PointerState::Idle => {
                 let hit = self.shapes.find_hovered(x, y);
@@ -70,17 +75,17 @@ impl Raymarching {
                 self.shapes.shapes[index].drag_move(press_position, [x as f32, y as f32]);
                 let shapes_data = self.shapes.buffer_data();
 
-                queue.write_buffer(
+                context.queue.write_buffer(
                     &self.shapes_buffers.shapes,
                     0,
                     bytemuck::cast_slice(&shapes_data.shapes),
                 );
-                queue.write_buffer(
+                context.queue.write_buffer(
                     &self.shapes_buffers.spheres,
                     0,
                     bytemuck::cast_slice(&shapes_data.spheres),
                 );
-                queue.write_buffer(
+                context.queue.write_buffer(
                     &self.shapes_buffers.rounded_boxes,
                     0,
                     bytemuck::cast_slice(&shapes_data.rounded_boxes),
@@ -94,7 +99,10 @@ impl Raymarching {
         };
         self.state = next_state;
     }
-    pub fn mouse_press(&mut self, queue: &wgpu::Queue) {
+    fn is_hovered(&self) -> bool {
+        self.state.is_hovered()
+    }
+    fn mouse_press(&mut self, queue: &wgpu::Queue) {
         let next_state = match self.state.take() {
             PointerState::Idle => PointerState::Idle,
             PointerState::Hovered {
@@ -104,24 +112,16 @@ impl Raymarching {
                 index,
                 press_position: hover_position,
             },
-            PointerState::Pressed {
-                index,
-                press_position,
-            } => todo!(),
-            PointerState::Dragging {
-                index,
-                press_position,
-            } => todo!(),
+            // Another button pressed mid-drag keeps the drag going
+            state @ (PointerState::Pressed { .. } | PointerState::Dragging { .. }) => state,
         };
         self.state = next_state;
     }
-    pub fn mouse_release(&mut self, queue: &wgpu::Queue) {
+    fn mouse_release(&mut self, queue: &wgpu::Queue) {
         let next_state = match self.state.take() {
             PointerState::Idle => PointerState::Idle,
-            PointerState::Hovered {
-                index,
-                hover_position,
-            } => todo!(),
+            // A release whose press went to another layer
+            state @ PointerState::Hovered { .. } => state,
             PointerState::Pressed {
                 index,
                 press_position,
@@ -139,40 +139,17 @@ impl Raymarching {
         };
         self.state = next_state;
     }
+}
+impl Raymarching {
     pub fn new(
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         system: &SystemGroup,
         wallpaper: &Wallpaper,
     ) -> Self {
-        let shapes = Shapes::new(vec![
-            Shape::Sphere(Sphere {
-                center: [100.0, 100.0, 64.0],
-                radius: 48.0,
-            }),
-            Shape::Sphere(Sphere {
-                center: [500.0, 500.0, 64.0],
-                radius: 48.0,
-            }),
-            Shape::RoundedBox(RoundedBox {
-                center: [250.0, 250.0, 64.0],
-                half_size: [200.0, 100.0, 48.0],
-                radius: 48.0,
-                ..Default::default()
-            }),
-            Shape::RoundedBox(RoundedBox {
-                center: [250.0, 50.0, 64.0],
-                half_size: [48.0, 48.0, 48.0],
-                radius: 48.0,
-                ..Default::default()
-            }),
-            Shape::Superformula(Superformula {
-                center: [250.0, 100.0, 64.0],
-                sides: 5.0,
-                radii: [128.0; 2],
-                exponents: [3.0; 3],
-            }),
-        ]);
+        let shapes = Shapes::new(
+            Shapes::parse(BUILT_IN_SCENE).expect("the built-in raymarching scene is valid"),
+        );
         let shapes_buffers = ShapesBuffers::new(&shapes, device);
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -362,6 +339,9 @@ impl Shapes {
     fn new(shapes: Vec<Shape>) -> Self {
         Self { shapes }
     }
+    fn parse(source: &str) -> Result<Vec<Shape>, ron::de::SpannedError> {
+        ron::from_str(source)
+    }
     pub fn check_hovered(&self, shape: usize, x: f64, y: f64) -> Option<[f32; 2]> {
         let [x, y] = [x as f32, y as f32];
         let b = self.shapes[shape].bounding_box();
@@ -434,6 +414,10 @@ struct ShapesData {
     superformulas: Vec<Superformula>,
 }
 
+/// The shapes the layer starts with, kept as RON like the glass scenes
+const BUILT_IN_SCENE: &str = include_str!("../../scenes/raymarching.ron");
+
+#[derive(serde::Deserialize)]
 enum Shape {
     Sphere(Sphere),
     RoundedBox(RoundedBox),
@@ -460,14 +444,6 @@ impl Shape {
     }
 }
 
-#[repr(u32)]
-enum ShapeKind {
-    None,
-    Sphere,
-    RoundedBox,
-    Superformula,
-}
-
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct ShapeId {
@@ -476,7 +452,7 @@ struct ShapeId {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, serde::Deserialize)]
 struct Sphere {
     center: [f32; 3],
     radius: f32,
@@ -501,9 +477,10 @@ impl Sphere {
 }
 
 #[repr(C)]
-#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, serde::Deserialize)]
 struct RoundedBox {
     center: [f32; 3],
+    #[serde(skip)]
     _padding: u32,
     half_size: [f32; 3],
     radius: f32,
@@ -527,7 +504,7 @@ impl RoundedBox {
 }
 
 #[repr(C)]
-#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable, serde::Deserialize)]
 struct Superformula {
     center: [f32; 3],
     sides: f32,
@@ -570,3 +547,14 @@ impl AABB {
         (min_x..max_x).contains(&x) && (min_y..max_y).contains(&y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BUILT_IN_SCENE, Shapes};
+
+    #[test]
+    fn built_in_scene_parses() {
+        let shapes = Shapes::parse(BUILT_IN_SCENE).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(shapes.len(), 5);
+    }
+}
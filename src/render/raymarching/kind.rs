@@ -0,0 +1,11 @@
+// Shaders import this as `util::raymarch::kind`, a module generated in memory by build.rs and the
+// runtime shader compiler. Also compiled into build.rs, so keep it free of other crate items
+
+EnumShaderConstants!(
+    pub enum ShapeKind {
+        None = 0,
+        Sphere = 1,
+        RoundedBox = 2,
+        Superformula = 3,
+    }
+);
@@ -0,0 +1,211 @@
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    pointer::PickMode,
+    shape::{
+        DEFAULT_BLEND_DISTANCE,
+        component::{Material, RGBA, ShapeKind, vec2f},
+    },
+    wallpaper::{Backdrop, GradientStop, WallpaperError, WallpaperFit},
+};
+
+/// A glass layer's shapes and the backdrop behind them, stored as RON so layouts can be
+/// edited without recompiling
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    /// Leaves the current backdrop alone when `None`
+    pub backdrop: Option<SceneBackdrop>,
+    /// Whether shapes merged by the smooth-min blend are picked as one outline
+    pub pick_mode: PickMode,
+    /// How far apart, in px, shapes start to merge
+    pub blend_distance: f32,
+    /// Back to front
+    pub shapes: Vec<SceneShape>,
+}
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            backdrop: None,
+            pick_mode: PickMode::default(),
+            blend_distance: DEFAULT_BLEND_DISTANCE,
+            shapes: Vec::new(),
+        }
+    }
+}
+impl Scene {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|source| SceneError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        Self::parse(&source).map_err(|source| SceneError::Parse {
+            path: path.to_owned(),
+            source,
+        })
+    }
+    pub fn parse(source: &str) -> Result<Self, ron::de::SpannedError> {
+        ron::from_str(source)
+    }
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        let source = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+            .map_err(SceneError::Serialize)?;
+        fs::write(path, source).map_err(|source| SceneError::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+}
+
+/// One shape, placed and styled, as it's inserted into a glass layer
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneShape {
+    pub geometry: ShapeGeometry,
+    pub center: vec2f,
+    /// Clockwise, in radians
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "SceneShape::default_scale")]
+    pub scale: vec2f,
+    pub tint: RGBA,
+    #[serde(default)]
+    pub material: Material,
+}
+impl SceneShape {
+    fn default_scale() -> vec2f {
+        [1.0; 2]
+    }
+}
+
+/// The kind of a shape and its kind-specific parameters, in pixels
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ShapeGeometry {
+    Circle {
+        radius: f32,
+    },
+    RoundedRect {
+        half_size: vec2f,
+        radius: f32,
+    },
+    Squircle {
+        half_size: vec2f,
+        exponent: f32,
+    },
+    Superformula {
+        sides: f32,
+        radii: vec2f,
+        exponents: [f32; 3],
+    },
+}
+impl ShapeGeometry {
+    pub fn kind(&self) -> ShapeKind {
+        match self {
+            Self::Circle { .. } => ShapeKind::Circle,
+            Self::RoundedRect { .. } => ShapeKind::RoundedRect,
+            Self::Squircle { .. } => ShapeKind::Squircle,
+            Self::Superformula { .. } => ShapeKind::Superformula,
+        }
+    }
+}
+
+/// The backdrops a scene file can describe, a subset of [`Backdrop`] without runtime
+/// resources
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SceneBackdrop {
+    /// An image or animation on disk, relative to the scene file
+    Wallpaper {
+        path: PathBuf,
+        #[serde(default)]
+        fit: WallpaperFit,
+    },
+    Solid(RGBA),
+    LinearGradient {
+        start: vec2f,
+        end: vec2f,
+        stops: Vec<GradientStop>,
+    },
+    RadialGradient {
+        center: vec2f,
+        radius: f32,
+        stops: Vec<GradientStop>,
+    },
+    Noise {
+        colors: [RGBA; 2],
+        scale: f32,
+        speed: f32,
+    },
+}
+impl SceneBackdrop {
+    /// `None` for wallpapers, which have to be loaded from disk instead
+    pub fn procedural(&self) -> Option<Backdrop> {
+        match self {
+            Self::Wallpaper { .. } => None,
+            &Self::Solid(color) => Some(Backdrop::Solid(color)),
+            Self::LinearGradient { start, end, stops } => Some(Backdrop::LinearGradient {
+                start: *start,
+                end: *end,
+                stops: stops.clone(),
+            }),
+            Self::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => Some(Backdrop::RadialGradient {
+                center: *center,
+                radius: *radius,
+                stops: stops.clone(),
+            }),
+            &Self::Noise {
+                colors,
+                scale,
+                speed,
+            } => Some(Backdrop::Noise {
+                colors,
+                scale,
+                speed,
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: ron::de::SpannedError,
+    },
+    Serialize(ron::Error),
+    Wallpaper(WallpaperError),
+}
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "couldn't access {}: {source}", path.display()),
+            Self::Parse { path, source } => write!(f, "invalid scene {}: {source}", path.display()),
+            Self::Serialize(err) => write!(f, "couldn't serialize the scene: {err}"),
+            Self::Wallpaper(err) => write!(f, "couldn't load the scene's wallpaper: {err}"),
+        }
+    }
+}
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            Self::Serialize(err) => Some(err),
+            Self::Wallpaper(err) => Some(err),
+        }
+    }
+}
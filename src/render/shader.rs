@@ -0,0 +1,124 @@
+//! Shaders are baked in by build.rs through `include_wesl!`. In development mode they're
+//! compiled from `src/shaders` at runtime instead, and pipelines are rebuilt whenever a
+//! source file is saved. Modules mirroring Rust enums, such as `shape::kind`, are generated
+//! in memory for both.
+
+use std::collections::HashMap;
+
+use super::{raymarching, shape::component::ShapeKind, watch::Watcher};
+use crate::macros::EnumShaderConstants;
+
+include!("shader/compiler.rs");
+include!("shader/constants.rs");
+
+/// The shaders whose pipelines can be rebuilt at runtime
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Shader {
+    Wallpaper,
+    GlassShapes,
+    SilhouetteSdf,
+    LightMaps,
+}
+impl Shader {
+    const ALL: [Self; 4] = [
+        Self::Wallpaper,
+        Self::GlassShapes,
+        Self::SilhouetteSdf,
+        Self::LightMaps,
+    ];
+
+    /// The root module, relative to the shader directory
+    fn root(self) -> &'static str {
+        match self {
+            Self::Wallpaper => "wallpaper.wesl",
+            Self::GlassShapes => "glass_shapes.wesl",
+            Self::SilhouetteSdf => "silhouette_sdf.wesl",
+            Self::LightMaps => "light_maps.wesl",
+        }
+    }
+}
+
+/// Recompiles the shaders when anything under the shader directory changes
+pub struct ShaderWatcher {
+    wesl: wesl::Wesl<ShaderResolver>,
+    watcher: Watcher,
+    /// The last WGSL each shader compiled to, so edits to shared modules only rebuild
+    /// the pipelines whose output actually changed. Empty at first, so the first save
+    /// rebuilds everything, including shaders edited since the last build.
+    compiled: HashMap<Shader, String>,
+}
+impl ShaderWatcher {
+    /// The shader sources in this checkout
+    pub const SOURCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+
+    /// Returns the shaders that compiled to new WGSL since the last call. Compile errors
+    /// are logged and the shader is left out, so its pipeline keeps the previous version.
+    pub fn poll(&mut self) -> Vec<(Shader, String)> {
+        if !self.watcher.poll() {
+            return Vec::new();
+        }
+        let mut changed = Vec::new();
+        for shader in Shader::ALL {
+            let source = match self.wesl.compile(shader.root()) {
+                Ok(result) => result.to_string(),
+                Err(err) => {
+                    log::error!("couldn't compile {}: {err}", shader.root());
+                    continue;
+                }
+            };
+            if self.compiled.get(&shader) != Some(&source) {
+                self.compiled.insert(shader, source.clone());
+                changed.push((shader, source));
+            }
+        }
+        changed
+    }
+
+    pub fn new(dir: &str) -> Self {
+        Self {
+            wesl: wesl_compiler(dir, enum_modules::<ShapeKind, raymarching::ShapeKind>()),
+            watcher: Watcher::new(dir),
+            compiled: HashMap::new(),
+        }
+    }
+}
+
+/// Builds a pipeline from freshly compiled WGSL, logging the validation errors and
+/// returning `None` if the shader or the pipeline is invalid
+pub fn try_create_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    source: &str,
+    create_pipeline: impl FnOnce(&wgpu::ShaderModule) -> wgpu::RenderPipeline,
+) -> Option<wgpu::RenderPipeline> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline = create_pipeline(&shader);
+    match pollster::block_on(device.pop_error_scope()) {
+        None => Some(pipeline),
+        Some(err) => {
+            log::error!("{label} is invalid, keeping the previous pipeline: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enum_modules_declare_every_variant() {
+        let modules = enum_modules::<ShapeKind, raymarching::ShapeKind>();
+        let (_, shape_kind) = modules
+            .iter()
+            .find(|(path, _)| *path == "shape/kind")
+            .unwrap();
+        assert!(shape_kind.contains("const CIRCLE = 1;\n"));
+        assert!(shape_kind.contains("const ROUNDED_RECT = 2;\n"));
+        assert_eq!(shape_kind.matches("const ").count(), 4);
+    }
+}
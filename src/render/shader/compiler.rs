@@ -0,0 +1,53 @@
+// Included by both build.rs and `render::shader`, so shaders compiled at runtime resolve
+// and mangle exactly like the ones baked in at build time
+
+/// The WESL compiler for the shaders under `base`, with `generated` modules, keyed by
+/// their path under `base`, served from memory
+fn wesl_compiler(
+    base: &str,
+    generated: Vec<(&'static str, String)>,
+) -> wesl::Wesl<ShaderResolver> {
+    let resolver = ShaderResolver {
+        files: wesl::StandardResolver::new(base),
+        generated,
+    };
+
+    let mut wesl = wesl::Wesl::new_barebones().set_custom_resolver(resolver);
+    wesl.set_mangler(wesl::ManglerKind::default())
+        .set_options(wesl::CompileOptions {
+            imports: true,
+            condcomp: true,
+            lower: true,
+            validate: true,
+            lazy: true,
+            ..Default::default()
+        });
+    wesl
+}
+
+/// Resolves modules from the shader directory, except for generated ones, which have no
+/// file so nothing is written into the source tree
+pub struct ShaderResolver {
+    files: wesl::StandardResolver,
+    generated: Vec<(&'static str, String)>,
+}
+impl wesl::Resolver for ShaderResolver {
+    fn resolve_source<'a>(
+        &'a self,
+        path: &wesl::ModulePath,
+    ) -> Result<std::borrow::Cow<'a, str>, wesl::ResolveError> {
+        let generated = self.generated.iter().find(|(module, _)| {
+            path.components
+                .iter()
+                .map(String::as_str)
+                .eq(module.split('/'))
+        });
+        match generated {
+            Some((_, source)) => Ok(source.into()),
+            None => self.files.resolve_source(path),
+        }
+    }
+    fn display_name(&self, path: &wesl::ModulePath) -> Option<String> {
+        self.files.display_name(path)
+    }
+}
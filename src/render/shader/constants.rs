@@ -0,0 +1,42 @@
+// Included by both build.rs and `render::shader`, so shaders compiled at runtime import the
+// same enum modules as the ones baked in at build time
+
+/// The WESL modules generated from Rust enums, by their path under the shader directory.
+/// The enums are type parameters because build.rs compiles its own copy of them.
+fn enum_modules<ShapeKind, RaymarchingShapeKind>() -> Vec<(&'static str, String)>
+where
+    ShapeKind: EnumShaderConstants,
+    RaymarchingShapeKind: EnumShaderConstants,
+{
+    vec![
+        (
+            "shape/kind",
+            wesl_constants_module::<ShapeKind>("src/render/shape/kind.rs"),
+        ),
+        (
+            "util/raymarch/kind",
+            wesl_constants_module::<RaymarchingShapeKind>("src/render/raymarching/kind.rs"),
+        ),
+    ]
+}
+
+/// The WESL module declaring the variants of a Rust enum as `SCREAMING_SNAKE_CASE`
+/// constants, for shaders that switch on the values directly
+fn wesl_constants_module<T: EnumShaderConstants>(source: &str) -> String {
+    let mut module = format!("// Generated from {source}\n\n");
+    for (name, value) in T::SHADER_CONSTANTS {
+        module += &format!("const {} = {value};\n", screaming_snake_case(name));
+    }
+    module
+}
+
+fn screaming_snake_case(name: &str) -> String {
+    let mut screaming = String::new();
+    for (i, c) in name.char_indices() {
+        if i > 0 && c.is_uppercase() {
+            screaming.push('_');
+        }
+        screaming.push(c.to_ascii_uppercase());
+    }
+    screaming
+}
@@ -1,17 +1,30 @@
-use column::GpuColumn;
-use component::{RGBA, ShapeKind, ShapeKindIndex, vec2f};
+use std::array;
+
+use wgpu::util::DeviceExt;
+
+use column::{GpuColumn, bind_group_layout_entry};
+use component::{Material, RGBA, ShapeId, ShapeKind, ShapeKindIndex, vec2f};
+use handle::ShapeHandles;
 use light_map::LightMaps;
+use sdf::SignedDistance;
 use silhouette::SilhouetteSdf;
 
 use super::{
-    pointer::{AABB, BoundingBox, PointerHit, PointerState},
+    pointer::{AABB, BoundingBox, PickMode, PointerHit, PointerState},
+    scene::{SceneShape, ShapeGeometry},
+    shader::Shader,
     system::SystemGroup,
     wallpaper::Wallpaper,
 };
 
 mod column;
 pub mod component;
+mod handle;
+mod kind;
 pub mod light_map;
+#[cfg(test)]
+mod reflection;
+mod sdf;
 pub mod silhouette;
 
 pub struct Shapes {
@@ -45,8 +58,50 @@ impl Shapes {
             state: Default::default(),
         }
     }
-    pub fn insert_circle(&mut self, center: vec2f, radius: f32, tint_color: RGBA) {
-        self.storage.insert_circle(center, radius, tint_color);
+    pub fn insert_circle(&mut self, center: vec2f, radius: f32, tint_color: RGBA) -> ShapeId {
+        self.storage.insert_circle(center, radius, tint_color)
+    }
+    pub fn insert_rounded_rect(
+        &mut self,
+        center: vec2f,
+        half_size: vec2f,
+        radius: f32,
+        tint_color: RGBA,
+    ) -> ShapeId {
+        self.storage
+            .insert_rounded_rect(center, half_size, radius, tint_color)
+    }
+    pub fn insert_squircle(
+        &mut self,
+        center: vec2f,
+        half_size: vec2f,
+        exponent: f32,
+        tint_color: RGBA,
+    ) -> ShapeId {
+        self.storage
+            .insert_squircle(center, half_size, exponent, tint_color)
+    }
+    pub fn insert_superformula(
+        &mut self,
+        center: vec2f,
+        sides: f32,
+        radii: vec2f,
+        exponents: [f32; 3],
+        tint_color: RGBA,
+    ) -> ShapeId {
+        self.storage
+            .insert_superformula(center, sides, radii, exponents, tint_color)
+    }
+    pub fn insert(&mut self, shape: &SceneShape) -> ShapeId {
+        self.storage.insert_scene_shape(shape)
+    }
+    /// Every shape back to front, as it would be inserted again
+    pub fn scene_shapes(&self) -> Vec<SceneShape> {
+        self.storage
+            .draw_order
+            .iter()
+            .map(|&shape| self.storage.scene_shape(shape))
+            .collect()
     }
     pub fn resize(
         &mut self,
@@ -71,6 +126,25 @@ impl Shapes {
             size,
         );
     }
+    /// Rebuilds the silhouette or light maps pipeline from `source` and regenerates the
+    /// textures with it
+    pub fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        system: &SystemGroup,
+        shader: Shader,
+        source: &str,
+    ) {
+        match shader {
+            Shader::SilhouetteSdf => self.silhouette.reload_shader(device, source),
+            Shader::LightMaps => self.light_maps.reload_shader(device, source),
+            Shader::Wallpaper | Shader::GlassShapes => return,
+        }
+        if self.bind_group.is_some() {
+            self.generate(device, queue, system);
+        }
+    }
     pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
         &self.bind_group_layout
     }
@@ -90,6 +164,77 @@ impl Shapes {
         self.light_maps.bind_group()
     }
 
+    pub fn pick_mode(&self) -> PickMode {
+        self.storage.pick_mode
+    }
+    pub fn set_pick_mode(&mut self, pick_mode: PickMode) {
+        self.storage.pick_mode = pick_mode;
+    }
+    pub fn blend_distance(&self) -> f32 {
+        self.storage.blend_distance
+    }
+    /// Sets how far apart, in px, shapes start to merge, both in the silhouette and for
+    /// picking in [`PickMode::Scene`]
+    pub fn set_blend_distance(&mut self, blend_distance: f32) {
+        // The blend divides by it, so it can't reach zero
+        self.storage.blend_distance = blend_distance.max(MIN_BLEND_DISTANCE);
+    }
+    pub fn raise(&mut self, id: ShapeId) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.raise(shape);
+        }
+    }
+    pub fn lower(&mut self, id: ShapeId) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.lower(shape);
+        }
+    }
+    pub fn bring_to_front(&mut self, id: ShapeId) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.bring_to_front(shape);
+        }
+    }
+    pub fn send_to_back(&mut self, id: ShapeId) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.send_to_back(shape);
+        }
+    }
+    pub fn set_center(&mut self, id: ShapeId, center: vec2f) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.position[shape].center = center;
+        }
+    }
+    pub fn set_rotation(&mut self, id: ShapeId, rotation: f32) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.position[shape].rotation = rotation;
+        }
+    }
+    pub fn set_scale(&mut self, id: ShapeId, scale: vec2f) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.position[shape].set_scale(scale);
+        }
+    }
+    pub fn set_material(&mut self, id: ShapeId, material: Material) {
+        if let Some(shape) = self.storage.handles.get(id) {
+            self.storage.material[shape] = material;
+        }
+    }
+    pub fn remove(&mut self, id: ShapeId) -> bool {
+        if !self.storage.remove(id) {
+            return false;
+        }
+        // Dense shape indices move on removal, so any hovered index is stale
+        self.state = PointerState::Idle;
+        true
+    }
+    /// Brings the shapes in line with `shapes`, listed back to front, so that only the
+    /// columns whose values changed are uploaded in the next [`Shapes::prepare`]
+    pub fn update(&mut self, shapes: &[SceneShape]) {
+        if self.storage.update_from_scene(shapes) {
+            self.state = PointerState::Idle;
+        }
+    }
+
     pub fn init_gpu(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, system: &SystemGroup) {
         self.storage.init_buffers(device);
         let bind_group = self.storage.bind_group(device, &self.bind_group_layout);
@@ -98,8 +243,33 @@ impl Shapes {
 
         self.bind_group = Some(bind_group);
     }
+    /// Per-frame sync point: uploads the shape changes made since the last call
+    /// and regenerates the silhouette and light maps if there were any
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, system: &SystemGroup) {
+        if self.bind_group.is_none() || !self.storage.is_dirty() {
+            return;
+        }
+        if self.storage.flush_buffers(device, queue) {
+            self.bind_group = Some(self.storage.bind_group(device, &self.bind_group_layout));
+        }
+        self.generate(device, queue, system);
+    }
+    fn generate(&self, device: &wgpu::Device, queue: &wgpu::Queue, system: &SystemGroup) {
+        self.silhouette
+            .generate(device, queue, system, self.bind_group());
+        self.light_maps.generate(
+            device,
+            queue,
+            system,
+            self.bind_group(),
+            self.silhouette.bind_group(),
+        );
+    }
 }
 impl Shapes {
+    pub fn is_hovered(&self) -> bool {
+        self.state.is_hovered()
+    }
     pub fn cursor_move(
         &mut self,
         device: &wgpu::Device,
@@ -147,16 +317,7 @@ impl Shapes {
                 press_position,
             } => {
                 self.storage
-                    .drag_move(queue, index as _, press_position, [x as _, y as _]);
-                self.silhouette
-                    .generate(device, queue, system, &self.bind_group());
-                self.light_maps.generate(
-                    device,
-                    queue,
-                    system,
-                    &self.bind_group(),
-                    self.silhouette.bind_group(),
-                );
+                    .drag_move(index as _, press_position, [x as _, y as _]);
 
                 PointerState::Dragging {
                     index,
@@ -171,27 +332,22 @@ impl Shapes {
             PointerState::Hovered {
                 index,
                 hover_position,
-            } => PointerState::Pressed {
-                index,
-                press_position: hover_position,
-            },
-            PointerState::Pressed {
-                index,
-                press_position,
-            } => todo!(),
-            PointerState::Dragging {
-                index,
-                press_position,
-            } => todo!(),
+            } => {
+                self.storage.bring_to_front(index as _);
+                PointerState::Pressed {
+                    index,
+                    press_position: hover_position,
+                }
+            }
+            // Another button pressed mid-drag keeps the drag going
+            state @ (PointerState::Pressed { .. } | PointerState::Dragging { .. }) => state,
         };
     }
     pub fn mouse_release(&mut self, queue: &wgpu::Queue) {
         self.state = match self.state.take() {
             PointerState::Idle => PointerState::Idle,
-            PointerState::Hovered {
-                index,
-                hover_position,
-            } => todo!(),
+            // A release whose press went to another layer
+            state @ PointerState::Hovered { .. } => state,
             PointerState::Pressed {
                 index,
                 press_position,
@@ -210,66 +366,120 @@ impl Shapes {
     }
 }
 
+/// Smooth-min blend radius in px that scenes start with
+pub const DEFAULT_BLEND_DISTANCE: f32 = 8.0;
+const MIN_BLEND_DISTANCE: f32 = 1e-3;
+
 struct ShapesStorage {
     index_map: GpuColumn<ShapeEntry>,
     position: GpuColumn<ShapePosition>,
     appearance: GpuColumn<ShapeAppearance>,
+    material: GpuColumn<Material>,
     circle: GpuColumn<Circle>,
+    rounded_rect: GpuColumn<RoundedRect>,
+    squircle: GpuColumn<Squircle>,
+    superformula: GpuColumn<Superformula>,
+    draw_order: GpuColumn<u32>,
+    handles: ShapeHandles,
+    /// Smooth-min blend radius in px, uploaded with the count so the silhouette and
+    /// picking agree
+    blend_distance: f32,
+    uniform_buffer: Option<wgpu::Buffer>,
+    uploaded_uniform: ShapesUniform,
+    pick_mode: PickMode,
 }
 impl ShapesStorage {
-    fn drag_move(
-        &mut self,
-        queue: &wgpu::Queue,
-        shape: u32,
-        press_position: [f32; 2],
-        cursor_position: [f32; 2],
-    ) {
+    fn drag_move(&mut self, shape: u32, press_position: [f32; 2], cursor_position: [f32; 2]) {
         let [x, y] = cursor_position;
         let [press_x, press_y] = press_position;
         let new_min_bound = [x - press_x, y - press_y].map(|d| d.round());
-        let entry = self.index_map[shape];
-        match entry.kind {
-            ShapeKind::Circle => {
-                let circle = &self.circle[entry.kind_index];
-                let [center_x, center_y] = new_min_bound.map(|d| d + circle.radius);
-                self.position[shape].center = [center_x, center_y];
-            }
-        }
-        self.position.update_buffer(queue);
+        let local_bounds = self.local_bounding_box(shape);
+        self.position[shape].center = array::from_fn(|i| new_min_bound[i] - local_bounds.min[i]);
     }
     fn find_hovered(&self, x: f64, y: f64) -> Option<PointerHit> {
         let [x, y] = [x as f32, y as f32];
-        self.index_map
-            .iter()
-            .enumerate()
-            .map(|(i, s)| {
-                (
-                    i,
-                    match s.kind {
-                        ShapeKind::Circle => self.circle[s.kind_index]
-                            .bounding_box()
-                            .with_center(self.position[i].center),
-                    },
-                )
-            })
-            .find_map(|(i, b)| {
-                Some(PointerHit {
-                    index: i,
-                    local_position: b.hit_test(x, y).then(|| b.local_point(x, y))?,
-                })
-            })
+        let shape = self.pick([x, y])?;
+        Some(PointerHit {
+            index: shape as _,
+            local_position: self.bounding_box(shape).local_point(x, y),
+        })
     }
     fn check_hovered(&self, shape: u32, x: f64, y: f64) -> Option<[f32; 2]> {
         let [x, y] = [x as f32, y as f32];
-        let b = self.bounding_box(shape);
-        b.hit_test(x, y).then(|| b.local_point(x, y))
+        // A shape raised above the hovered one takes over even while the pointer stays inside both
+        (self.pick([x, y]) == Some(shape)).then(|| self.bounding_box(shape).local_point(x, y))
+    }
+    fn pick(&self, p: vec2f) -> Option<u32> {
+        let topmost = || {
+            self.draw_order
+                .iter()
+                .rev()
+                .copied()
+                .find(|&shape| self.signed_distance(shape, p) <= 0.0)
+        };
+        match self.pick_mode {
+            PickMode::Shape => topmost(),
+            PickMode::Scene => {
+                // Mirrors the blending loop in silhouette_sdf.wesl
+                let mut scene: Option<(f32, u32, f32)> = None;
+                for &shape in &self.draw_order {
+                    let d = self.signed_distance(shape, p);
+                    scene = Some(match scene {
+                        None => (d, shape, d),
+                        Some((scene_d, nearest, nearest_d)) => {
+                            let (scene_d, _) = sdf::smin_blend(scene_d, d, self.blend_distance);
+                            if d < nearest_d {
+                                (scene_d, shape, d)
+                            } else {
+                                (scene_d, nearest, nearest_d)
+                            }
+                        }
+                    });
+                }
+                let (scene_d, nearest, _) = scene?;
+                // Merged regions outside every shape belong to whichever shape is nearest
+                (scene_d <= 0.0).then(|| topmost().unwrap_or(nearest))
+            }
+        }
+    }
+    fn signed_distance(&self, shape: u32, p: vec2f) -> f32 {
+        let entry = self.index_map[shape];
+        let position = &self.position[shape];
+        let local_p = position.local_point(p);
+        let distance = match entry.kind {
+            ShapeKind::Circle => self.circle[entry.kind_index].signed_distance(local_p),
+            ShapeKind::RoundedRect => self.rounded_rect[entry.kind_index].signed_distance(local_p),
+            ShapeKind::Squircle => self.squircle[entry.kind_index].signed_distance(local_p),
+            ShapeKind::Superformula => self.superformula[entry.kind_index].signed_distance(local_p),
+        };
+        distance * position.distance_scale()
     }
     fn bounding_box(&self, shape: u32) -> AABB {
+        self.local_bounding_box(shape)
+            .with_center(self.position[shape].center)
+    }
+    /// Bounds of the rotated and scaled shape, relative to its center
+    fn local_bounding_box(&self, shape: u32) -> AABB {
+        let AABB { min, max } = self.kind_bounding_box(shape);
+        let position = &self.position[shape];
+        let corners = [min, [max[0], min[1]], max, [min[0], max[1]]].map(|p| position.linear(p));
+        AABB {
+            min: array::from_fn(|i| corners.iter().map(|p| p[i]).fold(f32::INFINITY, f32::min)),
+            max: array::from_fn(|i| {
+                corners
+                    .iter()
+                    .map(|p| p[i])
+                    .fold(f32::NEG_INFINITY, f32::max)
+            }),
+        }
+    }
+    fn kind_bounding_box(&self, shape: u32) -> AABB {
         let entry = self.index_map[shape];
         match entry.kind {
-            ShapeKind::Circle => self.circle[entry.kind_index]
-                .bounding_box()
-                .with_center(self.position[shape].center),
+            ShapeKind::Circle => self.circle[entry.kind_index].bounding_box(),
+            ShapeKind::RoundedRect => self.rounded_rect[entry.kind_index].bounding_box(),
+            ShapeKind::Squircle => self.squircle[entry.kind_index].bounding_box(),
+            ShapeKind::Superformula => self.superformula[entry.kind_index].bounding_box(),
         }
     }
 }
@@ -279,20 +489,349 @@ impl ShapesStorage {
             index_map: GpuColumn::new(),
             position: GpuColumn::new(),
             appearance: GpuColumn::new(),
+            material: GpuColumn::new(),
             circle: GpuColumn::new(),
+            rounded_rect: GpuColumn::new(),
+            squircle: GpuColumn::new(),
+            superformula: GpuColumn::new(),
+            draw_order: GpuColumn::new(),
+            handles: ShapeHandles::default(),
+            blend_distance: DEFAULT_BLEND_DISTANCE,
+            uniform_buffer: None,
+            uploaded_uniform: ShapesUniform {
+                count: 0,
+                blend_distance: 0.0,
+            },
+            pick_mode: PickMode::default(),
         }
     }
-    fn insert_circle(&mut self, center: vec2f, radius: f32, tint_color: RGBA) {
+    fn insert_circle(&mut self, center: vec2f, radius: f32, tint_color: RGBA) -> ShapeId {
         let circle = Circle { radius };
         let circle_index = self.circle.insert(circle);
 
+        self.insert_shape(ShapeKind::Circle, circle_index, center, tint_color)
+    }
+    fn insert_rounded_rect(
+        &mut self,
+        center: vec2f,
+        half_size: vec2f,
+        radius: f32,
+        tint_color: RGBA,
+    ) -> ShapeId {
+        let rounded_rect = RoundedRect {
+            half_size,
+            radius,
+            _padding: 0,
+        };
+        let rounded_rect_index = self.rounded_rect.insert(rounded_rect);
+
+        self.insert_shape(
+            ShapeKind::RoundedRect,
+            rounded_rect_index,
+            center,
+            tint_color,
+        )
+    }
+    fn insert_squircle(
+        &mut self,
+        center: vec2f,
+        half_size: vec2f,
+        exponent: f32,
+        tint_color: RGBA,
+    ) -> ShapeId {
+        let squircle = Squircle {
+            half_size,
+            exponent,
+            _padding: 0,
+        };
+        let squircle_index = self.squircle.insert(squircle);
+
+        self.insert_shape(ShapeKind::Squircle, squircle_index, center, tint_color)
+    }
+    fn insert_superformula(
+        &mut self,
+        center: vec2f,
+        sides: f32,
+        radii: vec2f,
+        exponents: [f32; 3],
+        tint_color: RGBA,
+    ) -> ShapeId {
+        let superformula = Superformula {
+            exponents,
+            sides,
+            radii,
+            _padding: [0; 2],
+        };
+        let superformula_index = self.superformula.insert(superformula);
+
+        self.insert_shape(
+            ShapeKind::Superformula,
+            superformula_index,
+            center,
+            tint_color,
+        )
+    }
+    fn insert_scene_shape(&mut self, shape: &SceneShape) -> ShapeId {
+        let SceneShape {
+            geometry,
+            center,
+            rotation,
+            scale,
+            tint,
+            material,
+        } = *shape;
+        let id = match geometry {
+            ShapeGeometry::Circle { radius } => self.insert_circle(center, radius, tint),
+            ShapeGeometry::RoundedRect { half_size, radius } => {
+                self.insert_rounded_rect(center, half_size, radius, tint)
+            }
+            ShapeGeometry::Squircle {
+                half_size,
+                exponent,
+            } => self.insert_squircle(center, half_size, exponent, tint),
+            ShapeGeometry::Superformula {
+                sides,
+                radii,
+                exponents,
+            } => self.insert_superformula(center, sides, radii, exponents, tint),
+        };
+        let index = self.handles.get(id).expect("the shape was just inserted");
+        self.position[index].rotation = rotation;
+        self.position[index].set_scale(scale);
+        self.material[index] = material;
+        id
+    }
+    /// Shapes keep their place in the draw order for as long as their kind matches the
+    /// scene's, so moving or restyling one only rewrites the columns that differ. From
+    /// the first kind mismatch on, the remaining shapes are removed and inserted again.
+    /// Returns `true` if any shape was removed.
+    fn update_from_scene(&mut self, shapes: &[SceneShape]) -> bool {
+        let current: Vec<u32> = self.draw_order.iter().copied().collect();
+        let kept = current
+            .iter()
+            .zip(shapes)
+            .take_while(|&(&shape, new)| self.index_map[shape].kind == new.geometry.kind())
+            .count();
+
+        for (&shape, new) in current.iter().zip(shapes).take(kept) {
+            self.update_scene_shape(shape, new);
+        }
+        // Removal moves dense indices around, so go through the stable ids
+        let removed: Vec<ShapeId> = current[kept..]
+            .iter()
+            .map(|&shape| self.handles.id(shape))
+            .collect();
+        for &id in &removed {
+            self.remove(id);
+        }
+        for new in &shapes[kept..] {
+            self.insert_scene_shape(new);
+        }
+        !removed.is_empty()
+    }
+    fn update_scene_shape(&mut self, shape: u32, new: &SceneShape) {
+        let old = self.scene_shape(shape);
+        if (old.center, old.rotation, old.scale) != (new.center, new.rotation, new.scale) {
+            let position = &mut self.position[shape];
+            position.center = new.center;
+            position.rotation = new.rotation;
+            position.set_scale(new.scale);
+        }
+        if old.tint != new.tint {
+            self.appearance[shape].tint_color = new.tint;
+        }
+        if old.material != new.material {
+            self.material[shape] = new.material;
+        }
+        if old.geometry != new.geometry {
+            let kind_index = self.index_map[shape].kind_index;
+            match new.geometry {
+                ShapeGeometry::Circle { radius } => self.circle[kind_index] = Circle { radius },
+                ShapeGeometry::RoundedRect { half_size, radius } => {
+                    self.rounded_rect[kind_index] = RoundedRect {
+                        half_size,
+                        radius,
+                        _padding: 0,
+                    }
+                }
+                ShapeGeometry::Squircle {
+                    half_size,
+                    exponent,
+                } => {
+                    self.squircle[kind_index] = Squircle {
+                        half_size,
+                        exponent,
+                        _padding: 0,
+                    }
+                }
+                ShapeGeometry::Superformula {
+                    sides,
+                    radii,
+                    exponents,
+                } => {
+                    self.superformula[kind_index] = Superformula {
+                        exponents,
+                        sides,
+                        radii,
+                        _padding: [0; 2],
+                    }
+                }
+            }
+        }
+    }
+    fn scene_shape(&self, shape: u32) -> SceneShape {
+        let entry = self.index_map[shape];
+        let geometry = match entry.kind {
+            ShapeKind::Circle => {
+                let Circle { radius } = self.circle[entry.kind_index];
+                ShapeGeometry::Circle { radius }
+            }
+            ShapeKind::RoundedRect => {
+                let RoundedRect {
+                    half_size, radius, ..
+                } = self.rounded_rect[entry.kind_index];
+                ShapeGeometry::RoundedRect { half_size, radius }
+            }
+            ShapeKind::Squircle => {
+                let Squircle {
+                    half_size,
+                    exponent,
+                    ..
+                } = self.squircle[entry.kind_index];
+                ShapeGeometry::Squircle {
+                    half_size,
+                    exponent,
+                }
+            }
+            ShapeKind::Superformula => {
+                let Superformula {
+                    exponents,
+                    sides,
+                    radii,
+                    ..
+                } = self.superformula[entry.kind_index];
+                ShapeGeometry::Superformula {
+                    sides,
+                    radii,
+                    exponents,
+                }
+            }
+        };
+        let position = &self.position[shape];
+        SceneShape {
+            geometry,
+            center: position.center,
+            rotation: position.rotation,
+            scale: position.scale,
+            tint: self.appearance[shape].tint_color,
+            material: self.material[shape],
+        }
+    }
+    fn insert_shape(
+        &mut self,
+        kind: ShapeKind,
+        kind_index: u32,
+        center: vec2f,
+        tint_color: RGBA,
+    ) -> ShapeId {
         let shape = ShapeEntry {
-            kind: ShapeKind::Circle,
-            kind_index: circle_index.into(),
+            kind,
+            kind_index: kind_index.into(),
         };
-        self.index_map.insert(shape);
-        self.position.insert(ShapePosition { center });
+        let shape_index = self.index_map.insert(shape);
+        self.position.insert(ShapePosition::new(center));
         self.appearance.insert(ShapeAppearance { tint_color });
+        self.material.insert(Material::default());
+        self.draw_order.insert(shape_index);
+
+        self.handles.insert(shape_index)
+    }
+    fn remove(&mut self, id: ShapeId) -> bool {
+        let Some(shape) = self.handles.swap_remove(id) else {
+            return false;
+        };
+        let entry = self.index_map.swap_remove(shape);
+        self.position.swap_remove(shape);
+        self.appearance.swap_remove(shape);
+        self.material.swap_remove(shape);
+
+        if let Some(depth) = self.depth(shape) {
+            self.draw_order.remove(depth);
+        }
+        // The last shape took the removed shape's place
+        let moved_shape = self.index_map.len() as u32;
+        if moved_shape != shape
+            && let Some(moved_depth) = self.depth(moved_shape)
+        {
+            self.draw_order[moved_depth] = shape;
+        }
+
+        let kind_index = entry.kind_index;
+        let moved_kind_index = match entry.kind {
+            ShapeKind::Circle => {
+                self.circle.swap_remove(kind_index.into());
+                self.circle.len()
+            }
+            ShapeKind::RoundedRect => {
+                self.rounded_rect.swap_remove(kind_index.into());
+                self.rounded_rect.len()
+            }
+            ShapeKind::Squircle => {
+                self.squircle.swap_remove(kind_index.into());
+                self.squircle.len()
+            }
+            ShapeKind::Superformula => {
+                self.superformula.swap_remove(kind_index.into());
+                self.superformula.len()
+            }
+        };
+        // Likewise the last item of the kind column took the removed item's place
+        let moved_kind_index = ShapeKindIndex::from(moved_kind_index as u32);
+        if let Some(moved) = (0..self.index_map.len() as u32).find(|&i| {
+            self.index_map[i].kind == entry.kind && self.index_map[i].kind_index == moved_kind_index
+        }) {
+            self.index_map[moved].kind_index = kind_index;
+        }
+        true
+    }
+    /// Position of `shape` in the back-to-front draw order, `None` for a shape index
+    /// that's out of range, so the z-order operations do nothing for it
+    fn depth(&self, shape: u32) -> Option<u32> {
+        let depth = self.draw_order.iter().position(|&s| s == shape)?;
+        Some(depth as _)
+    }
+    fn raise(&mut self, shape: u32) {
+        let Some(depth) = self.depth(shape) else {
+            return;
+        };
+        if depth + 1 < self.draw_order.len() as u32 {
+            self.draw_order.swap(depth, depth + 1);
+        }
+    }
+    fn lower(&mut self, shape: u32) {
+        let Some(depth) = self.depth(shape) else {
+            return;
+        };
+        if depth > 0 {
+            self.draw_order.swap(depth, depth - 1);
+        }
+    }
+    fn bring_to_front(&mut self, shape: u32) {
+        let Some(depth) = self.depth(shape) else {
+            return;
+        };
+        if depth + 1 < self.draw_order.len() as u32 {
+            self.draw_order.remove(depth);
+            self.draw_order.insert(shape);
+        }
+    }
+    fn send_to_back(&mut self, shape: u32) {
+        let Some(depth) = self.depth(shape) else {
+            return;
+        };
+        for d in (0..depth).rev() {
+            self.draw_order.swap(d, d + 1);
+        }
     }
     fn init_buffers(&mut self, device: &wgpu::Device) {
         self.index_map.init_buffer(
@@ -311,6 +850,89 @@ impl ShapesStorage {
             device,
             wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         );
+        self.rounded_rect.init_buffer(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        self.squircle.init_buffer(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        self.superformula.init_buffer(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        self.draw_order.init_buffer(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        self.material.init_buffer(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        self.uploaded_uniform = self.uniform();
+        self.uniform_buffer = Some(
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("shapes uniform buffer"),
+                contents: bytemuck::bytes_of(&self.uploaded_uniform),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }),
+        );
+    }
+    fn is_dirty(&self) -> bool {
+        self.uniform() != self.uploaded_uniform
+            || self.index_map.is_dirty()
+            || self.position.is_dirty()
+            || self.appearance.is_dirty()
+            || self.circle.is_dirty()
+            || self.rounded_rect.is_dirty()
+            || self.squircle.is_dirty()
+            || self.superformula.is_dirty()
+            || self.draw_order.is_dirty()
+            || self.material.is_dirty()
+    }
+    /// Uploads the changed parts of every column, growing buffers that are too small.
+    /// Returns `true` if any buffer was reallocated and the bind group must be rebuilt.
+    fn flush_buffers(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let reallocated = self.index_map.reserve_buffer(device)
+            | self.position.reserve_buffer(device)
+            | self.appearance.reserve_buffer(device)
+            | self.circle.reserve_buffer(device)
+            | self.rounded_rect.reserve_buffer(device)
+            | self.squircle.reserve_buffer(device)
+            | self.superformula.reserve_buffer(device)
+            | self.draw_order.reserve_buffer(device)
+            | self.material.reserve_buffer(device);
+
+        self.index_map.flush_buffer(queue);
+        self.position.flush_buffer(queue);
+        self.appearance.flush_buffer(queue);
+        self.circle.flush_buffer(queue);
+        self.rounded_rect.flush_buffer(queue);
+        self.squircle.flush_buffer(queue);
+        self.superformula.flush_buffer(queue);
+        self.draw_order.flush_buffer(queue);
+        self.material.flush_buffer(queue);
+
+        if self.uniform() != self.uploaded_uniform {
+            self.uploaded_uniform = self.uniform();
+            queue.write_buffer(
+                self.uniform_buffer.as_ref().unwrap(),
+                0,
+                bytemuck::bytes_of(&self.uploaded_uniform),
+            );
+        }
+
+        reallocated
+    }
+    fn count(&self) -> u32 {
+        self.index_map.len() as _
+    }
+    fn uniform(&self) -> ShapesUniform {
+        ShapesUniform {
+            count: self.count(),
+            blend_distance: self.blend_distance,
+        }
     }
     fn bind_group(
         &self,
@@ -324,6 +946,15 @@ impl ShapesStorage {
                 self.position.bind_group_entry(1),
                 self.appearance.bind_group_entry(2),
                 self.circle.bind_group_entry(3),
+                self.rounded_rect.bind_group_entry(4),
+                self.squircle.bind_group_entry(5),
+                self.superformula.bind_group_entry(6),
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: self.uniform_buffer.as_ref().unwrap().as_entire_binding(),
+                },
+                self.draw_order.bind_group_entry(8),
+                self.material.bind_group_entry(9),
             ],
             label: Some("shapes bind group"),
         })
@@ -331,52 +962,40 @@ impl ShapesStorage {
     fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
+                bind_group_layout_entry(0),
+                bind_group_layout_entry(1),
+                bind_group_layout_entry(2),
+                bind_group_layout_entry(3),
+                bind_group_layout_entry(4),
+                bind_group_layout_entry(5),
+                bind_group_layout_entry(6),
                 wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
+                    binding: 7,
                     visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
                     count: None,
                 },
+                bind_group_layout_entry(8),
+                bind_group_layout_entry(9),
             ],
             label: Some("shapes bind group layout"),
         })
     }
 }
 
+/// What every shape query needs besides the columns
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ShapesUniform {
+    count: u32,
+    /// Smooth-min blend radius in px
+    blend_distance: f32,
+}
+
 #[derive(Copy, Clone, Debug, bytemuck::NoUninit, bytemuck::CheckedBitPattern)]
 #[repr(C)]
 struct ShapeEntry {
@@ -384,10 +1003,55 @@ struct ShapeEntry {
     kind_index: ShapeKindIndex,
 }
 
+/// Maps the shape's local space onto the screen: scale first, then rotation, then translation to `center`
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 struct ShapePosition {
     center: vec2f,
+    scale: vec2f,
+    /// Clockwise on screen, in radians
+    rotation: f32,
+    _padding: u32,
+}
+impl ShapePosition {
+    const fn new(center: vec2f) -> Self {
+        Self {
+            center,
+            scale: [1.0; 2],
+            rotation: 0.0,
+            _padding: 0,
+        }
+    }
+    /// Smallest scale magnitude on either axis, so the shape can always be mapped back to
+    /// local space
+    const MIN_SCALE: f32 = 1e-3;
+
+    /// Keeps the sign of each axis, so negative scales still mirror the shape
+    fn set_scale(&mut self, scale: vec2f) {
+        self.scale = scale.map(|s| {
+            if s.abs() < Self::MIN_SCALE {
+                Self::MIN_SCALE.copysign(s)
+            } else {
+                s
+            }
+        });
+    }
+    /// Applies rotation and scale, leaving out the translation
+    fn linear(&self, [x, y]: vec2f) -> vec2f {
+        let [x, y] = [x * self.scale[0], y * self.scale[1]];
+        let (sin, cos) = self.rotation.sin_cos();
+        [x * cos - y * sin, x * sin + y * cos]
+    }
+    fn local_point(&self, p: vec2f) -> vec2f {
+        let [x, y] = array::from_fn(|i| p[i] - self.center[i]);
+        let (sin, cos) = self.rotation.sin_cos();
+        let [x, y] = [x * cos + y * sin, y * cos - x * sin];
+        [x / self.scale[0], y / self.scale[1]]
+    }
+    /// Local distances stretch by at most this much, so scaling by it keeps them a lower bound
+    fn distance_scale(&self) -> f32 {
+        self.scale[0].abs().min(self.scale[1].abs())
+    }
 }
 
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -401,6 +1065,11 @@ struct ShapeAppearance {
 struct Circle {
     radius: f32,
 }
+impl SignedDistance for Circle {
+    fn signed_distance(&self, p: vec2f) -> f32 {
+        sdf::circle_sd(p, self.radius)
+    }
+}
 impl BoundingBox for Circle {
     fn bounding_box(&self) -> super::pointer::AABB {
         AABB {
@@ -409,3 +1078,206 @@ impl BoundingBox for Circle {
         }
     }
 }
+
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct RoundedRect {
+    half_size: vec2f,
+    radius: f32,
+    _padding: u32,
+}
+impl SignedDistance for RoundedRect {
+    fn signed_distance(&self, p: vec2f) -> f32 {
+        sdf::round_rect_sd(p, self.half_size, self.radius)
+    }
+}
+impl BoundingBox for RoundedRect {
+    fn bounding_box(&self) -> AABB {
+        AABB {
+            min: self.half_size.map(|d| -d),
+            max: self.half_size,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct Squircle {
+    half_size: vec2f,
+    exponent: f32,
+    _padding: u32,
+}
+impl SignedDistance for Squircle {
+    fn signed_distance(&self, p: vec2f) -> f32 {
+        sdf::superellipse_sd(p, self.half_size, self.exponent)
+    }
+}
+impl BoundingBox for Squircle {
+    fn bounding_box(&self) -> AABB {
+        AABB {
+            min: self.half_size.map(|d| -d),
+            max: self.half_size,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct Superformula {
+    exponents: [f32; 3],
+    sides: f32,
+    radii: vec2f,
+    _padding: [u32; 2],
+}
+impl Superformula {
+    const BOUNDARY_SAMPLES: usize = 256;
+
+    /// Radius of the boundary along `angle` in radii-normalized space,
+    /// i.e. where `superformula_sd` crosses zero
+    fn boundary_radius(&self, angle: f32) -> f32 {
+        let [_, n2, n3] = self.exponents;
+        let t = (self.sides / 4.0) * angle;
+        let [c, s] = [t.cos().abs(), t.sin().abs()];
+        let k = |r: f32| (r * c).powf(n2) + (r * s).powf(n3);
+
+        let mut hi = 1.0;
+        while k(hi) < 1.0 && hi < 1e6 {
+            hi *= 2.0;
+        }
+        let mut lo = 0.0;
+        for _ in 0..24 {
+            let mid = (lo + hi) / 2.0;
+            if k(mid) < 1.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        hi
+    }
+}
+impl SignedDistance for Superformula {
+    fn signed_distance(&self, p: vec2f) -> f32 {
+        sdf::superformula_sd(p, self.sides, self.radii, self.exponents)
+    }
+}
+impl BoundingBox for Superformula {
+    fn bounding_box(&self) -> AABB {
+        let extent = (0..Self::BOUNDARY_SAMPLES)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / Self::BOUNDARY_SAMPLES as f32;
+                let r = self.boundary_radius(angle);
+                [angle.cos(), angle.sin()].map(|d| (r * d).abs())
+            })
+            .fold([0.0f32; 2], |[x, y], [dx, dy]| [x.max(dx), y.max(dy)]);
+        let half_size: vec2f = array::from_fn(|i| extent[i] * self.radii[i]);
+
+        AABB {
+            min: half_size.map(|d| -d),
+            max: half_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(center: vec2f, radius: f32) -> SceneShape {
+        SceneShape {
+            geometry: ShapeGeometry::Circle { radius },
+            center,
+            rotation: 0.0,
+            scale: [1.0; 2],
+            tint: 0xffffffff.into(),
+            material: Material::default(),
+        }
+    }
+    fn rounded_rect(center: vec2f) -> SceneShape {
+        SceneShape {
+            geometry: ShapeGeometry::RoundedRect {
+                half_size: [40.0, 20.0],
+                radius: 8.0,
+            },
+            ..circle(center, 0.0)
+        }
+    }
+
+    fn storage(shapes: &[SceneShape]) -> ShapesStorage {
+        let mut storage = ShapesStorage::new();
+        for shape in shapes {
+            storage.insert_scene_shape(shape);
+        }
+        storage
+    }
+    fn scene_shapes(storage: &ShapesStorage) -> Vec<SceneShape> {
+        storage
+            .draw_order
+            .iter()
+            .map(|&shape| storage.scene_shape(shape))
+            .collect()
+    }
+    fn ids(storage: &ShapesStorage) -> Vec<ShapeId> {
+        storage
+            .draw_order
+            .iter()
+            .map(|&shape| storage.handles.id(shape))
+            .collect()
+    }
+
+    #[test]
+    fn matching_kinds_are_updated_in_place() {
+        let mut storage = storage(&[circle([0.0; 2], 10.0), rounded_rect([50.0; 2])]);
+        let before = ids(&storage);
+
+        let scene = [circle([5.0, 6.0], 12.0), rounded_rect([70.0; 2])];
+        assert!(!storage.update_from_scene(&scene));
+        assert_eq!(ids(&storage), before);
+        assert_eq!(scene_shapes(&storage), scene);
+    }
+
+    #[test]
+    fn shapes_from_the_first_kind_mismatch_on_are_replaced() {
+        let mut storage = storage(&[
+            circle([0.0; 2], 10.0),
+            circle([20.0; 2], 10.0),
+            circle([40.0; 2], 10.0),
+        ]);
+        let before = ids(&storage);
+
+        let scene = [circle([0.0; 2], 10.0), rounded_rect([20.0; 2])];
+        assert!(storage.update_from_scene(&scene));
+        assert_eq!(storage.handles.get(before[0]), Some(0));
+        assert_eq!(storage.handles.get(before[1]), None);
+        assert_eq!(storage.handles.get(before[2]), None);
+        assert_eq!(scene_shapes(&storage), scene);
+    }
+
+    #[test]
+    fn extra_scene_shapes_are_inserted_on_top() {
+        let mut storage = storage(&[circle([0.0; 2], 10.0)]);
+        let before = ids(&storage);
+
+        let scene = [circle([0.0; 2], 10.0), rounded_rect([20.0; 2])];
+        assert!(!storage.update_from_scene(&scene));
+        assert_eq!(ids(&storage)[0], before[0]);
+        assert_eq!(scene_shapes(&storage), scene);
+
+        assert!(storage.update_from_scene(&[]));
+        assert!(scene_shapes(&storage).is_empty());
+    }
+
+    #[test]
+    fn zero_scale_is_clamped() {
+        let flat = SceneShape {
+            scale: [0.0, -0.0],
+            ..circle([0.0; 2], 10.0)
+        };
+        let storage = storage(&[flat]);
+        assert_eq!(
+            scene_shapes(&storage)[0].scale,
+            [ShapePosition::MIN_SCALE, -ShapePosition::MIN_SCALE]
+        );
+        assert!(storage.signed_distance(0, [5.0, 5.0]).is_finite());
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
-    ops::{Index, IndexMut},
+    mem,
+    ops::{Index, IndexMut, Range},
     slice, vec,
 };
 
@@ -26,6 +27,15 @@ impl<T> Column<T> {
         self.items.push(item);
         return index as _;
     }
+    pub fn swap_remove(&mut self, index: u32) -> T {
+        self.items.swap_remove(index as _)
+    }
+    pub fn remove(&mut self, index: u32) -> T {
+        self.items.remove(index as _)
+    }
+    pub fn swap(&mut self, a: u32, b: u32) {
+        self.items.swap(a as _, b as _);
+    }
 }
 impl<T> Column<T>
 where
@@ -38,17 +48,84 @@ where
 
 pub struct GpuColumn<T> {
     column: Column<T>,
-    buffer: Option<wgpu::Buffer>,
+    buffer: Option<ColumnBuffer>,
+    dirty: Vec<Range<usize>>,
+}
+struct ColumnBuffer {
+    buffer: wgpu::Buffer,
+    usage: wgpu::BufferUsages,
+    capacity: usize,
 }
 impl<T> GpuColumn<T> {
     pub const fn new() -> Self {
         Self {
             column: Column::new(),
             buffer: None,
+            dirty: Vec::new(),
         }
     }
     pub fn insert(&mut self, item: T) -> u32 {
-        self.column.insert(item)
+        let index = self.column.insert(item);
+        self.mark_dirty(index as _);
+        index
+    }
+    pub fn swap_remove(&mut self, index: u32) -> T {
+        let item = self.column.swap_remove(index);
+        if (index as usize) < self.len() {
+            self.mark_dirty(index as _);
+        }
+        item
+    }
+    /// Removes an item while keeping the order of the rest
+    pub fn remove(&mut self, index: u32) -> T {
+        let item = self.column.remove(index);
+        self.mark_dirty_range(index as usize..self.len());
+        item
+    }
+    pub fn swap(&mut self, a: u32, b: u32) {
+        self.column.swap(a, b);
+        self.mark_dirty(a as _);
+        self.mark_dirty(b as _);
+    }
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+    fn mark_dirty(&mut self, index: usize) {
+        self.mark_dirty_range(index..index + 1);
+    }
+    fn mark_dirty_range(&mut self, range: Range<usize>) {
+        // Repeated writes to the same items (e.g. dragging) collapse into the last range
+        if let Some(last) = self.dirty.last_mut()
+            && range.start <= last.end
+            && last.start <= range.end
+        {
+            last.start = last.start.min(range.start);
+            last.end = last.end.max(range.end);
+            return;
+        }
+        self.dirty.push(range);
+    }
+    /// Sorted, non-overlapping dirty ranges clamped to the current length
+    fn take_dirty_ranges(&mut self) -> Vec<Range<usize>> {
+        let len = self.len();
+        let mut dirty = mem::take(&mut self.dirty);
+        dirty.sort_unstable_by_key(|range| range.start);
+
+        let mut ranges: Vec<Range<usize>> = Vec::with_capacity(dirty.len());
+        for range in dirty {
+            let range = range.start..range.end.min(len);
+            if range.is_empty() {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        ranges
+    }
+    pub fn len(&self) -> usize {
+        self.column.items.len()
     }
     pub fn iter(&self) -> slice::Iter<T> {
         self.column.items.iter()
@@ -59,21 +136,75 @@ where
     T: bytemuck::NoUninit,
 {
     pub fn init_buffer(&mut self, device: &wgpu::Device, usage: wgpu::BufferUsages) {
-        self.buffer = Some(
+        let contents = self.column.as_bytes();
+        // Storage bindings can't be empty, so keep room for one item even when there are none
+        let buffer = if contents.is_empty() {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(std::any::type_name::<T>()),
+                size: mem::size_of::<T>() as _,
+                usage,
+                mapped_at_creation: false,
+            })
+        } else {
             device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some(std::any::type_name::<T>()),
-                contents: self.column.as_bytes(),
+                contents,
                 usage,
-            }),
-        );
+            })
+        };
+        self.buffer = Some(ColumnBuffer {
+            buffer,
+            usage,
+            capacity: self.column.items.len().max(1),
+        });
+        self.dirty.clear();
+    }
+    /// Reallocates the buffer if the column has outgrown it.
+    /// Returns `true` when it did, in which case bind groups referencing the old buffer must be rebuilt.
+    pub fn reserve_buffer(&mut self, device: &wgpu::Device) -> bool {
+        let len = self.column.items.len();
+        let column_buffer = self.buffer.as_mut().unwrap();
+        if len <= column_buffer.capacity {
+            return false;
+        }
+
+        let capacity = len.max(column_buffer.capacity * 2);
+        column_buffer.buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(std::any::type_name::<T>()),
+            size: (capacity * mem::size_of::<T>()) as _,
+            usage: column_buffer.usage,
+            mapped_at_creation: false,
+        });
+        column_buffer.capacity = capacity;
+
+        self.dirty.clear();
+        self.mark_dirty_range(0..len);
+        true
     }
-    pub fn update_buffer(&mut self, queue: &wgpu::Queue) {
-        queue.write_buffer(&self.buffer.as_ref().unwrap(), 0, self.column.as_bytes());
+    /// Writes the items changed since the last flush to the buffer
+    pub fn flush_buffer(&mut self, queue: &wgpu::Queue) {
+        let ranges = self.take_dirty_ranges();
+        let column_buffer = self.buffer.as_ref().unwrap();
+        assert!(
+            self.column.items.len() <= column_buffer.capacity,
+            "column outgrew its buffer, call reserve_buffer first"
+        );
+
+        let bytes = self.column.as_bytes();
+        let item_size = mem::size_of::<T>();
+        for range in ranges {
+            let bytes_range = range.start * item_size..range.end * item_size;
+            queue.write_buffer(
+                &column_buffer.buffer,
+                bytes_range.start as _,
+                &bytes[bytes_range],
+            );
+        }
     }
     pub fn bind_group_entry(&self, binding: u32) -> wgpu::BindGroupEntry {
         wgpu::BindGroupEntry {
             binding,
-            resource: self.buffer.as_ref().unwrap().as_entire_binding(),
+            resource: self.buffer.as_ref().unwrap().buffer.as_entire_binding(),
         }
     }
 }
@@ -86,6 +217,7 @@ impl<T> Index<usize> for GpuColumn<T> {
 }
 impl<T> IndexMut<usize> for GpuColumn<T> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.mark_dirty(index);
         &mut self.column.items[index]
     }
 }
@@ -135,3 +267,73 @@ pub const fn bind_group_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry
         count: None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GpuColumn;
+
+    fn column(len: u32) -> GpuColumn<u32> {
+        let mut column = GpuColumn::new();
+        for i in 0..len {
+            column.insert(i);
+        }
+        column.take_dirty_ranges();
+        column
+    }
+    /// The ranges a flush would write, as `[start, end]` pairs
+    fn flush(column: &mut GpuColumn<u32>) -> Vec<[usize; 2]> {
+        let ranges = column.take_dirty_ranges();
+        ranges
+            .iter()
+            .map(|range| [range.start, range.end])
+            .collect()
+    }
+
+    #[test]
+    fn repeated_writes_collapse_into_one_range() {
+        let mut column = column(8);
+        column[3u32] = 10;
+        column[3u32] = 11;
+        column[4u32] = 12;
+        assert_eq!(column.dirty.len(), 1);
+        assert_eq!(flush(&mut column), [[3, 5]]);
+    }
+
+    #[test]
+    fn ranges_are_sorted_and_merged_on_flush() {
+        let mut column = column(8);
+        column[6u32] = 1;
+        column[1u32] = 1;
+        column[5u32] = 1;
+        column[2u32] = 1;
+        assert_eq!(flush(&mut column), [[1, 3], [5, 7]]);
+        assert!(!column.is_dirty());
+    }
+
+    #[test]
+    fn ranges_are_clamped_to_the_current_length() {
+        let mut column = column(4);
+        column[0u32] = 1;
+        column[3u32] = 1;
+        column.swap_remove(3);
+        assert_eq!(flush(&mut column), [[0, 1]]);
+    }
+
+    #[test]
+    fn remove_rewrites_the_items_after_it() {
+        let mut column = column(4);
+        column.remove(1);
+        assert_eq!(flush(&mut column), [[1, 3]]);
+        assert_eq!(column.iter().copied().collect::<Vec<_>>(), [0, 2, 3]);
+    }
+
+    #[test]
+    fn swap_remove_of_the_last_item_writes_nothing() {
+        let mut column = column(4);
+        column.swap_remove(3);
+        assert!(!column.is_dirty());
+        column.swap_remove(0);
+        assert_eq!(flush(&mut column), [[0, 1]]);
+        assert_eq!(column[0u32], 2);
+    }
+}
@@ -1,7 +1,11 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
 #[allow(non_camel_case_types)]
 pub type vec2f = [f32; 2];
 
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(transparent)]
 pub struct RGBA([f32; 4]);
 
@@ -11,22 +15,83 @@ impl From<u32> for RGBA {
         RGBA(rgba.map(|c| c as f32 / 255.0))
     }
 }
-
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-#[repr(transparent)]
-pub struct ShapeId(u32);
-impl From<u32> for ShapeId {
-    fn from(value: u32) -> Self {
-        Self(value)
+impl From<RGBA> for u32 {
+    fn from(RGBA(rgba): RGBA) -> Self {
+        u32::from_be_bytes(rgba.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+    }
+}
+/// Parses `#rrggbb` or `#rrggbbaa`, the `#` is optional
+impl FromStr for RGBA {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let invalid = || format!("invalid color {s}, expected #rrggbb or #rrggbbaa");
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        match hex.len() {
+            6 => Ok((value << 8 | 0xFF).into()),
+            8 => Ok(value.into()),
+            _ => Err(invalid()),
+        }
     }
 }
-EnumShaderConstants!(
-    pub enum ShapeKind {
-        Circle = 1,
+/// Colors are stored as `#rrggbbaa` strings so scene files stay readable
+impl Serialize for RGBA {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("#{:08x}", u32::from(*self)))
     }
-);
+}
+impl<'de> Deserialize<'de> for RGBA {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// How a glass shape bends and colors the backdrop behind it
+#[derive(
+    Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable, Serialize, Deserialize,
+)]
+#[serde(default)]
+#[repr(C)]
+pub struct Material {
+    /// Index of refraction
+    pub ior: f32,
+    /// Radius of the rounded edge, in pixels
+    pub bevel_radius: f32,
+    /// Half the height of the extruded body before the bevel rounds it, in pixels
+    pub thickness: f32,
+    /// Spread of the per-channel indices of refraction, as a fraction of `ior`
+    pub dispersion: f32,
+    /// Saturation of the refracted backdrop, 1 leaves it unchanged
+    pub saturation: f32,
+    /// Radius of the frosted blur applied to the backdrop, in pixels, 0 keeps it sharp
+    pub blur_radius: f32,
+}
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            ior: 1.5,
+            bevel_radius: 32.0,
+            thickness: 32.0,
+            dispersion: 0.1,
+            saturation: 1.5,
+            blur_radius: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ShapeId {
+    pub(super) slot: u32,
+    pub(super) generation: u32,
+}
+pub use super::kind::ShapeKind;
 
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(transparent)]
 pub struct ShapeKindIndex(u32);
 impl From<u32> for ShapeKindIndex {
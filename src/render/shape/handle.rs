@@ -0,0 +1,112 @@
+use super::component::ShapeId;
+
+/// Maps generational [`ShapeId`]s to dense shape indices, which move around
+/// when shapes are swap-removed from the storage columns
+#[derive(Default)]
+pub struct ShapeHandles {
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    shape_slots: Vec<u32>,
+}
+struct Slot {
+    generation: u32,
+    shape: Option<u32>,
+}
+impl ShapeHandles {
+    pub fn insert(&mut self, shape: u32) -> ShapeId {
+        debug_assert_eq!(shape as usize, self.shape_slots.len());
+
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot as usize].shape = Some(shape);
+                slot
+            }
+            None => {
+                let slot = self.slots.len();
+                assert!(slot <= u32::MAX as _);
+
+                self.slots.push(Slot {
+                    generation: 0,
+                    shape: Some(shape),
+                });
+                slot as _
+            }
+        };
+        self.shape_slots.push(slot);
+
+        ShapeId {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        }
+    }
+    pub fn get(&self, id: ShapeId) -> Option<u32> {
+        let slot = self.slots.get(id.slot as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.shape
+    }
+    /// The id of the shape at dense index `shape`
+    pub fn id(&self, shape: u32) -> ShapeId {
+        let slot = self.shape_slots[shape as usize];
+        ShapeId {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        }
+    }
+    /// Invalidates `id` and returns the dense index of its shape, which the
+    /// caller must swap-remove from every shape column
+    pub fn swap_remove(&mut self, id: ShapeId) -> Option<u32> {
+        let shape = self.get(id)?;
+
+        let slot = &mut self.slots[id.slot as usize];
+        slot.shape = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(id.slot);
+
+        self.shape_slots.swap_remove(shape as usize);
+        if let Some(&moved_slot) = self.shape_slots.get(shape as usize) {
+            self.slots[moved_slot as usize].shape = Some(shape);
+        }
+        Some(shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShapeHandles;
+
+    #[test]
+    fn removed_ids_go_stale() {
+        let mut handles = ShapeHandles::default();
+        let a = handles.insert(0);
+        assert_eq!(handles.swap_remove(a), Some(0));
+        assert_eq!(handles.get(a), None);
+        assert_eq!(handles.swap_remove(a), None);
+    }
+
+    #[test]
+    fn reused_slots_get_a_new_generation() {
+        let mut handles = ShapeHandles::default();
+        let a = handles.insert(0);
+        handles.swap_remove(a);
+        let b = handles.insert(0);
+        assert_eq!(b.slot, a.slot);
+        assert_ne!(b.generation, a.generation);
+        assert_eq!(handles.get(a), None);
+        assert_eq!(handles.get(b), Some(0));
+    }
+
+    #[test]
+    fn swap_remove_follows_the_moved_shape() {
+        let mut handles = ShapeHandles::default();
+        let a = handles.insert(0);
+        let b = handles.insert(1);
+        let c = handles.insert(2);
+        assert_eq!(handles.swap_remove(a), Some(0));
+        assert_eq!(handles.get(c), Some(0));
+        assert_eq!(handles.get(b), Some(1));
+        assert_eq!(handles.id(0), c);
+        assert_eq!(handles.id(1), b);
+    }
+}
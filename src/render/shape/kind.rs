@@ -0,0 +1,11 @@
+// Shaders import this as `shape::kind`, a module generated in memory by build.rs and the
+// runtime shader compiler. Also compiled into build.rs, so keep it free of other crate items
+
+EnumShaderConstants!(
+    pub enum ShapeKind {
+        Circle = 1,
+        RoundedRect = 2,
+        Squircle = 3,
+        Superformula = 4,
+    }
+);
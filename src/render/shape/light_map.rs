@@ -1,9 +1,10 @@
 use wesl::include_wesl;
 
-use crate::render::system::SystemGroup;
+use crate::render::{shader, system::SystemGroup};
 
 pub struct LightMaps {
     bindings: LightMapsGroup,
+    pipeline_layout: wgpu::PipelineLayout,
     pipeline: wgpu::RenderPipeline,
 }
 impl LightMaps {
@@ -14,6 +15,17 @@ impl LightMaps {
         &self.bindings.read_bind_group_layout
     }
 
+    /// Swaps in a pipeline built from `source`, keeping the current one if it's invalid.
+    /// The textures keep their contents until the next [`LightMaps::generate`].
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        if let Some(pipeline) =
+            shader::try_create_pipeline(device, "light maps shader", source, |shader| {
+                Self::create_pipeline(device, &self.pipeline_layout, shader)
+            })
+        {
+            self.pipeline = pipeline;
+        }
+    }
     pub fn resize(
         &mut self,
         device: &wgpu::Device,
@@ -79,11 +91,24 @@ impl LightMaps {
             bind_group_layouts: &[&system.bind_group_layout, shapes_layout, silhouette_layout],
             push_constant_ranges: &[],
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let pipeline = Self::create_pipeline(device, &pipeline_layout, &shader);
+
+        Self {
+            bindings,
+            pipeline_layout,
+            pipeline,
+        }
+    }
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("light maps pipeline"),
-            layout: Some(&pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 buffers: &[],
                 compilation_options: wgpu::PipelineCompilationOptions {
@@ -91,7 +116,7 @@ impl LightMaps {
                 },
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: wgpu::TextureFormat::Rgba8Unorm,
@@ -120,9 +145,7 @@ impl LightMaps {
             },
             multiview: None,
             cache: None,
-        });
-
-        Self { bindings, pipeline }
+        })
     }
 }
 
@@ -0,0 +1,211 @@
+//! Checks the `#[repr(C)]` types uploaded to the GPU against the structs the compiled
+//! shaders declare for the same bindings, so the two can't drift apart silently.
+//! Members are compared in order, skipping Rust padding fields.
+
+use std::mem::{offset_of, size_of};
+
+use naga::{
+    AddressSpace, ResourceBinding, TypeInner,
+    proc::{Layouter, TypeLayout},
+};
+use wesl::include_wesl;
+
+use super::{
+    Circle, RoundedRect, ShapeAppearance, ShapeEntry, ShapePosition, ShapesUniform, Squircle,
+    Superformula, component::Material,
+};
+use crate::render::system::{Cursor, Viewport};
+
+/// The Rust side of a binding's layout
+struct RustLayout {
+    name: &'static str,
+    size: usize,
+    /// Name, offset and size of every field that isn't padding
+    fields: Vec<(&'static str, usize, usize)>,
+}
+
+macro_rules! rust_layout {
+    ($Type:ident { $($field:ident),* $(,)? }) => {
+        RustLayout {
+            name: stringify!($Type),
+            size: size_of::<$Type>(),
+            fields: vec![$((
+                stringify!($field),
+                offset_of!($Type, $field),
+                field_size(|value: &$Type| &value.$field),
+            )),*],
+        }
+    };
+}
+fn field_size<T, F>(_: impl Fn(&T) -> &F) -> usize {
+    size_of::<F>()
+}
+
+#[test]
+fn gpu_types_match_shader_layouts() {
+    let bindings = [
+        (0, 0, rust_layout!(Viewport { size_px, size, px })),
+        (0, 1, rust_layout!(Cursor { position })),
+        (1, 0, rust_layout!(ShapeEntry { kind, kind_index })),
+        (
+            1,
+            1,
+            rust_layout!(ShapePosition {
+                center,
+                scale,
+                rotation
+            }),
+        ),
+        (1, 2, rust_layout!(ShapeAppearance { tint_color })),
+        (1, 3, rust_layout!(Circle { radius })),
+        (1, 4, rust_layout!(RoundedRect { half_size, radius })),
+        (
+            1,
+            5,
+            rust_layout!(Squircle {
+                half_size,
+                exponent
+            }),
+        ),
+        (
+            1,
+            6,
+            rust_layout!(Superformula {
+                exponents,
+                sides,
+                radii
+            }),
+        ),
+        (
+            1,
+            7,
+            rust_layout!(ShapesUniform {
+                count,
+                blend_distance
+            }),
+        ),
+        (
+            1,
+            9,
+            rust_layout!(Material {
+                ior,
+                bevel_radius,
+                thickness,
+                dispersion,
+                saturation,
+                blur_radius,
+            }),
+        ),
+    ];
+    let shaders = [
+        ("wallpaper", include_wesl!("wallpaper")),
+        ("glass", include_wesl!("glass")),
+        ("glass_shapes", include_wesl!("glass_shapes")),
+        ("silhouette_sdf", include_wesl!("silhouette_sdf")),
+        ("light_maps", include_wesl!("light_maps")),
+    ];
+    let modules: Vec<_> = shaders
+        .iter()
+        .map(|(name, source)| {
+            let module = naga::front::wgsl::parse_str(source)
+                .unwrap_or_else(|err| panic!("{name}: {}", err.emit_to_string(source)));
+            let mut layouter = Layouter::default();
+            layouter
+                .update(module.to_ctx())
+                .unwrap_or_else(|err| panic!("{name}: {err}"));
+            (module, layouter)
+        })
+        .collect();
+
+    let mut mismatches = Vec::new();
+    for (group, binding, rust) in &bindings {
+        let binding = ResourceBinding {
+            group: *group,
+            binding: *binding,
+        };
+        let Some((module, layouter, var)) = modules.iter().find_map(|(module, layouter)| {
+            let (_, var) = module
+                .global_variables
+                .iter()
+                .find(|(_, var)| var.binding.as_ref() == Some(&binding))?;
+            Some((module, layouter, var))
+        }) else {
+            mismatches.push(format!(
+                "{}: no shader declares @group({}) @binding({})",
+                rust.name, binding.group, binding.binding
+            ));
+            continue;
+        };
+        // Storage buffers hold runtime-sized arrays of the type, uniforms hold it directly
+        let ty = match (var.space, &module.types[var.ty].inner) {
+            (AddressSpace::Storage { .. }, &TypeInner::Array { base, stride, .. }) => {
+                if stride as usize != rust.size {
+                    mismatches.push(format!(
+                        "{}: array stride is {stride} in WGSL but the Rust size is {}",
+                        rust.name, rust.size
+                    ));
+                }
+                base
+            }
+            _ => var.ty,
+        };
+        check_layout(module, layouter, ty, rust, &mut mismatches);
+    }
+    assert!(
+        mismatches.is_empty(),
+        "Rust and WGSL layouts disagree:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+fn check_layout(
+    module: &naga::Module,
+    layouter: &Layouter,
+    ty: naga::Handle<naga::Type>,
+    rust: &RustLayout,
+    mismatches: &mut Vec<String>,
+) {
+    let TypeLayout { size, alignment } = layouter[ty];
+    let wgsl_name = module.types[ty].name.as_deref().unwrap_or("?");
+    let name = format!("{} ({wgsl_name})", rust.name);
+
+    if size as usize != rust.size {
+        mismatches.push(format!(
+            "{name}: size is {size} in WGSL but {} in Rust",
+            rust.size
+        ));
+    }
+    if !alignment.is_aligned(rust.size as u32) {
+        mismatches.push(format!(
+            "{name}: Rust size {} isn't a multiple of the WGSL alignment {alignment}",
+            rust.size
+        ));
+    }
+
+    let TypeInner::Struct { members, .. } = &module.types[ty].inner else {
+        mismatches.push(format!("{name}: not a struct in WGSL"));
+        return;
+    };
+    if members.len() != rust.fields.len() {
+        mismatches.push(format!(
+            "{name}: {} members in WGSL but {} fields in Rust",
+            members.len(),
+            rust.fields.len()
+        ));
+    }
+    for (member, &(field, offset, field_size)) in members.iter().zip(&rust.fields) {
+        let member_name = member.name.as_deref().unwrap_or("?");
+        if member.offset as usize != offset {
+            mismatches.push(format!(
+                "{name}: `{member_name}` is at offset {} in WGSL but `{field}` is at {offset} in Rust",
+                member.offset
+            ));
+        }
+        let member_size = layouter[member.ty].size as usize;
+        if member_size != field_size {
+            mismatches.push(format!(
+                "{name}: `{member_name}` is {member_size} bytes in WGSL but `{field}` is {field_size} in Rust",
+            ));
+        }
+    }
+}
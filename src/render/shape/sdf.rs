@@ -0,0 +1,133 @@
+//! CPU mirrors of the 2D distance functions in `shaders/shape/sdf/`, used for pointer picking.
+//! Distances are in the same units as their inputs, so pixels for the shapes storage.
+
+use std::array;
+
+use super::component::vec2f;
+
+pub trait SignedDistance {
+    fn signed_distance(&self, p: vec2f) -> f32;
+}
+
+fn length(v: vec2f) -> f32 {
+    v[0].hypot(v[1])
+}
+fn abs(v: vec2f) -> vec2f {
+    v.map(f32::abs)
+}
+fn div(a: vec2f, b: vec2f) -> vec2f {
+    array::from_fn(|i| a[i] / b[i])
+}
+
+// shape/norm/lp.wesl
+fn length2_p(v: vec2f, p: f32) -> f32 {
+    let [x, y] = abs(v).map(|d| d.powf(p));
+    (x + y).powf(1.0 / p)
+}
+fn length2_p3(v: vec2f, p: [f32; 3]) -> f32 {
+    let [x, y] = abs(v);
+    (x.powf(p[1]) + y.powf(p[2])).powf(1.0 / p[0])
+}
+
+// shape/norm/lpm.wesl
+fn length2_p3m(v: vec2f, p: [f32; 3], m: f32) -> f32 {
+    let phi = v[1].atan2(v[0]);
+    let r = length(v);
+    let t = (m / 4.0) * phi;
+    length2_p3([r * t.cos(), r * t.sin()], p)
+}
+
+// shape/sdf/exact.wesl
+pub fn circle_sd(p: vec2f, r: f32) -> f32 {
+    length(p) - r
+}
+pub fn rect_sd(p: vec2f, b: vec2f) -> f32 {
+    let d: vec2f = array::from_fn(|i| p[i].abs() - b[i]);
+    length(d.map(|d| d.max(0.0))) + d[0].max(d[1]).min(0.0)
+}
+pub fn round_rect_sd(p: vec2f, b: vec2f, radius: f32) -> f32 {
+    let r = radius.min(b[0].min(b[1]));
+    rect_sd(p, b.map(|d| d - r)) - r
+}
+
+// shape/sdf/lp_norm.wesl
+pub fn superellipse_sd(p: vec2f, r: vec2f, n: f32) -> f32 {
+    let k1 = length2_p(div(p, r), n);
+    let k2 = length2_p(div(p, array::from_fn(|i| r[i] * r[i])), n);
+    // The gradient estimate is undefined at the exact center
+    if k2 == 0.0 {
+        return -r[0].min(r[1]);
+    }
+    k1 * (k1 - 1.0) / k2
+}
+
+// shape/sdf/lpm_norm.wesl
+pub fn superformula_sd(p: vec2f, m: f32, r: vec2f, n: [f32; 3]) -> f32 {
+    let k1 = length2_p3m(div(p, r), n, m);
+    let k2 = length2_p3m(div(p, array::from_fn(|i| r[i] * r[i])), n, m);
+    if k2 == 0.0 {
+        return -r[0].min(r[1]);
+    }
+    k1 * (k1 - 1.0) / k2
+}
+
+// shape/sdf/blend.wesl
+/// Returns the blended distance and the weight of `b`
+pub fn smin_blend(a: f32, b: f32, k: f32) -> (f32, f32) {
+    let h = 1.0 - ((a - b).abs() / (6.0 * k)).min(1.0);
+    let w = h * h * h;
+    let m = w * 0.5;
+    let s = w * k;
+    if a < b { (a - s, m) } else { (b - s, 1.0 - m) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn exact_distances() {
+        assert_near(circle_sd([3.0, 4.0], 1.0), 4.0);
+        assert_near(circle_sd([0.0, 0.0], 2.0), -2.0);
+        assert_near(rect_sd([3.0, 0.0], [1.0, 1.0]), 2.0);
+        assert_near(rect_sd([2.0, 2.0], [1.0, 1.0]), 2f32.sqrt());
+        assert_near(rect_sd([0.0, 0.0], [2.0, 1.0]), -1.0);
+        assert_near(round_rect_sd([3.0, 0.0], [2.0, 1.0], 0.5), 1.0);
+        assert_near(
+            round_rect_sd([2.0, 1.0], [2.0, 1.0], 0.5),
+            0.5f32.hypot(0.5) - 0.5,
+        );
+    }
+
+    #[test]
+    fn norms_of_two_reduce_to_a_circle() {
+        assert_near(superellipse_sd([2.0, 0.0], [1.0, 1.0], 2.0), 1.0);
+        assert_near(superellipse_sd([0.0, 0.5], [1.0, 1.0], 2.0), -0.5);
+        assert_near(superformula_sd([0.0, 3.0], 4.0, [1.0, 1.0], [2.0; 3]), 2.0);
+    }
+
+    #[test]
+    fn center_is_inside_rather_than_nan() {
+        assert_near(superellipse_sd([0.0, 0.0], [3.0, 2.0], 4.0), -2.0);
+        assert_near(
+            superformula_sd([0.0, 0.0], 5.0, [3.0, 2.0], [1.0, 2.0, 2.0]),
+            -2.0,
+        );
+    }
+
+    #[test]
+    fn blend_only_changes_nearby_distances() {
+        assert_eq!(smin_blend(0.0, 10.0, 1.0), (0.0, 0.0));
+        assert_eq!(smin_blend(10.0, 0.0, 1.0), (0.0, 1.0));
+        let (d, weight) = smin_blend(0.0, 0.0, 1.0);
+        assert_near(d, -1.0);
+        assert_near(weight, 0.5);
+    }
+}
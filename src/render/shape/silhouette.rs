@@ -1,9 +1,10 @@
 use wesl::include_wesl;
 
-use crate::render::system::SystemGroup;
+use crate::render::{shader, system::SystemGroup};
 
 pub struct SilhouetteSdf {
     bindings: SilhouetteSdfGroup,
+    pipeline_layout: wgpu::PipelineLayout,
     pipeline: wgpu::RenderPipeline,
 }
 impl SilhouetteSdf {
@@ -14,6 +15,17 @@ impl SilhouetteSdf {
         &self.bindings.read_bind_group_layout
     }
 
+    /// Swaps in a pipeline built from `source`, keeping the current one if it's invalid.
+    /// The textures keep their contents until the next [`SilhouetteSdf::generate`].
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        if let Some(pipeline) =
+            shader::try_create_pipeline(device, "silhouette sdf shader", source, |shader| {
+                Self::create_pipeline(device, &self.pipeline_layout, shader)
+            })
+        {
+            self.pipeline = pipeline;
+        }
+    }
     pub fn resize(
         &mut self,
         device: &wgpu::Device,
@@ -32,7 +44,8 @@ impl SilhouetteSdf {
         system: &SystemGroup,
         shapes: &wgpu::BindGroup,
     ) {
-        let [sdf_view, tint_color_view] = self.bindings.textures.views();
+        let [sdf_view, tint_color_view, geometry_view, optics_view] =
+            self.bindings.textures.views();
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("silhouette sdf render encoder"),
@@ -57,6 +70,22 @@ impl SilhouetteSdf {
                             store: wgpu::StoreOp::Store,
                         },
                     }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &geometry_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: &optics_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }),
                 ],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
@@ -87,11 +116,24 @@ impl SilhouetteSdf {
             bind_group_layouts: &[&system.bind_group_layout, shapes_layout],
             push_constant_ranges: &[],
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let pipeline = Self::create_pipeline(device, &pipeline_layout, &shader);
+
+        Self {
+            bindings,
+            pipeline_layout,
+            pipeline,
+        }
+    }
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("silhouette sdf pipeline"),
-            layout: Some(&pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 buffers: &[],
                 compilation_options: wgpu::PipelineCompilationOptions {
@@ -99,7 +141,7 @@ impl SilhouetteSdf {
                 },
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 targets: &[
                     Some(wgpu::ColorTargetState {
@@ -112,6 +154,16 @@ impl SilhouetteSdf {
                         blend: None,
                         write_mask: Default::default(),
                     }),
+                    Some(wgpu::ColorTargetState {
+                        format: wgpu::TextureFormat::Rgba16Float,
+                        blend: None,
+                        write_mask: Default::default(),
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: wgpu::TextureFormat::Rgba16Float,
+                        blend: None,
+                        write_mask: Default::default(),
+                    }),
                 ],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
@@ -135,9 +187,7 @@ impl SilhouetteSdf {
             },
             multiview: None,
             cache: None,
-        });
-
-        Self { bindings, pipeline }
+        })
     }
 }
 struct SilhouetteSdfGroup {
@@ -167,6 +217,10 @@ impl SilhouetteSdfGroup {
 struct SilhouetteSdfTextures {
     sdf: wgpu::Texture,
     tint_color: wgpu::Texture,
+    /// Blended bevel radius and thickness of the shape materials
+    geometry: wgpu::Texture,
+    /// Blended IOR, dispersion, saturation and blur radius of the shape materials
+    optics: wgpu::Texture,
 }
 impl SilhouetteSdfTextures {
     fn new(device: &wgpu::Device, [width, height]: [u32; 2]) -> Self {
@@ -198,10 +252,43 @@ impl SilhouetteSdfTextures {
             label: None,
             view_formats: &[],
         });
+        let geometry = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: None,
+            view_formats: &[],
+        });
+        let optics = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: None,
+            view_formats: &[],
+        });
 
-        Self { sdf, tint_color }
+        Self {
+            sdf,
+            tint_color,
+            geometry,
+            optics,
+        }
     }
-    fn views(&self) -> [wgpu::TextureView; 2] {
+    fn views(&self) -> [wgpu::TextureView; 4] {
         let sdf_view = self
             .sdf
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -210,7 +297,15 @@ impl SilhouetteSdfTextures {
             .tint_color
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        [sdf_view, tint_color_view]
+        let geometry_view = self
+            .geometry
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let optics_view = self
+            .optics
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        [sdf_view, tint_color_view, geometry_view, optics_view]
     }
     fn read_bind_group(
         &self,
@@ -243,6 +338,32 @@ impl SilhouetteSdfTextures {
             ..Default::default()
         });
 
+        let geometry_view = self
+            .geometry
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let geometry_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let optics_view = self
+            .optics
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let optics_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout,
             entries: &[
@@ -262,6 +383,22 @@ impl SilhouetteSdfTextures {
                     binding: 3,
                     resource: wgpu::BindingResource::Sampler(&tint_color_sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&geometry_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::Sampler(&geometry_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(&optics_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::Sampler(&optics_sampler),
+                },
             ],
             label: Some("read silhouette sdf textures bind group"),
         })
@@ -301,6 +438,38 @@ impl SilhouetteSdfTextures {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
             ],
             label: Some("read silhouette sdf textures bind group layout"),
         })
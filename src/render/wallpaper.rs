@@ -1,10 +1,48 @@
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
+};
+
+use animation::Playback;
+use backdrop::BackdropPipeline;
+use blur::BackdropBlur;
+use downsample::{Downsample, DownsampleFilter, full_mip_level_count};
+use image::{
+    AnimationDecoder, ImageDecoder,
+    codecs::{gif::GifDecoder, png::PngDecoder},
+};
 use wesl::include_wesl;
+use wgpu::util::DeviceExt;
+
+use super::{layer::Layer, shader, system::SystemGroup};
 
-use super::system::SystemGroup;
+pub use animation::AnimationFrame;
+pub use backdrop::{Backdrop, BackdropColorSpace, GradientStop};
+
+mod animation;
+mod backdrop;
+mod blur;
+mod downsample;
 
 pub struct Wallpaper {
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
     pipeline: wgpu::RenderPipeline,
     pub texture: WallpaperTexture,
+    backdrop_pipeline: BackdropPipeline,
+    /// Copies texture backdrops, converting the ones holding sRGB encoded values
+    copy: Downsample,
+    srgb_decode: Downsample,
+    /// The current backdrop if it is rendered or copied on the GPU rather than uploaded
+    procedural: Option<Backdrop>,
+    /// Frames of an animated image backdrop, uploaded one at a time as they come due
+    animation: Option<Playback>,
+    looping: bool,
+    size: [u32; 2],
+    start: Instant,
 }
 impl Wallpaper {
     pub fn draw(&self, render_pass: &mut wgpu::RenderPass, system: &SystemGroup) {
@@ -20,6 +58,13 @@ impl Wallpaper {
         system: &SystemGroup,
     ) -> Self {
         let texture = WallpaperTexture::new(device, queue);
+        let backdrop_pipeline = BackdropPipeline::new(device, WallpaperTexture::FORMAT);
+        let copy = Downsample::new(device, WallpaperTexture::FORMAT, DownsampleFilter::Box);
+        let srgb_decode = Downsample::new(
+            device,
+            WallpaperTexture::FORMAT,
+            DownsampleFilter::SrgbDecode,
+        );
 
         let wallpaper_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("wallpaper shader"),
@@ -32,20 +77,48 @@ impl Wallpaper {
                 push_constant_ranges: &[],
             });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let pipeline = Self::create_pipeline(
+            device,
+            &wallpaper_render_pipeline_layout,
+            config.format,
+            &wallpaper_shader,
+        );
+
+        Self {
+            pipeline_layout: wallpaper_render_pipeline_layout,
+            format: config.format,
+            pipeline,
+            texture,
+            backdrop_pipeline,
+            copy,
+            srgb_decode,
+            procedural: None,
+            animation: None,
+            looping: true,
+            size: [config.width, config.height],
+            start: Instant::now(),
+        }
+    }
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        shader: &wgpu::ShaderModule,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("wallpaper render pipeline"),
-            layout: Some(&wallpaper_render_pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &wallpaper_shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 buffers: &[],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: &wallpaper_shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -71,74 +144,231 @@ impl Wallpaper {
             },
             multiview: None,
             cache: None,
-        });
+        })
+    }
+    /// Swaps in a pipeline built from `source`, keeping the current one if it's invalid
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        if let Some(pipeline) =
+            shader::try_create_pipeline(device, "wallpaper shader", source, |shader| {
+                Self::create_pipeline(device, &self.pipeline_layout, self.format, shader)
+            })
+        {
+            self.pipeline = pipeline;
+        }
+    }
+    /// Re-renders or uploads the next frame of animated backdrops, call once per frame
+    /// before drawing. The glass samples the same texture, so it refracts every new frame.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Some(animation) = &mut self.animation
+            && animation.advance(Instant::now())
+        {
+            self.texture
+                .replace(device, queue, animation.current_frame());
+        }
+        if self
+            .procedural
+            .as_ref()
+            .is_some_and(|backdrop| backdrop.is_animated())
+        {
+            self.render_procedural(device, queue);
+        }
+    }
+    /// Procedural backdrops are rendered at the viewport size, so they follow it
+    pub fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, size: [u32; 2]) {
+        self.size = size;
+        if self
+            .procedural
+            .as_ref()
+            .is_some_and(|backdrop| !matches!(backdrop, Backdrop::TextureSnapshot { .. }))
+        {
+            self.render_procedural(device, queue);
+        }
+    }
+    /// Replaces what the wallpaper shows. Images must fit within the device's
+    /// `max_texture_dimension_2d`, which [`Wallpaper::load`] checks.
+    pub fn set_backdrop(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, backdrop: Backdrop) {
+        self.procedural = None;
+        self.animation = None;
+        match backdrop {
+            Backdrop::Image(image) => {
+                self.texture.replace(device, queue, &image);
+            }
+            Backdrop::Animation(frames) => {
+                let mut animation = Playback::new(frames);
+                animation.set_looping(self.looping);
+                self.texture
+                    .replace(device, queue, animation.current_frame());
+                self.animation = Some(animation);
+            }
+            backdrop => {
+                self.procedural = Some(backdrop);
+                self.render_procedural(device, queue);
+            }
+        }
+    }
+    /// Takes a new snapshot of a [`Backdrop::TextureSnapshot`] after the caller has drawn
+    /// new content into its texture, which must already be submitted. Each call copies the
+    /// whole texture and rebuilds the mips and blur. Does nothing for other backdrops.
+    pub fn refresh_backdrop_snapshot(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if matches!(self.procedural, Some(Backdrop::TextureSnapshot { .. })) {
+            self.render_procedural(device, queue);
+        }
+    }
+    /// Resumes an animated backdrop, does nothing for still ones
+    pub fn play(&mut self) {
+        if let Some(animation) = &mut self.animation {
+            animation.play();
+        }
+    }
+    pub fn pause(&mut self) {
+        if let Some(animation) = &mut self.animation {
+            animation.pause();
+        }
+    }
+    pub fn is_playing(&self) -> bool {
+        self.animation
+            .as_ref()
+            .is_some_and(|animation| animation.is_playing())
+    }
+    /// Whether animated backdrops start over after the last frame, which is the default.
+    /// Otherwise they pause on it.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+        if let Some(animation) = &mut self.animation {
+            animation.set_looping(looping);
+        }
+    }
+    fn render_procedural(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let Some(backdrop) = &self.procedural else {
+            return;
+        };
+        let level_view = |texture: &wgpu::Texture| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                base_mip_level: 0,
+                mip_level_count: Some(1),
+                ..Default::default()
+            })
+        };
+        if let Backdrop::TextureSnapshot {
+            view,
+            size,
+            color_space,
+        } = backdrop
+        {
+            let copy = match color_space {
+                BackdropColorSpace::Linear => &self.copy,
+                BackdropColorSpace::Srgb => &self.srgb_decode,
+            };
+            self.texture
+                .render(device, queue, size.map(|d| d.max(1)), |encoder, texture| {
+                    copy.blit(device, encoder, view, &level_view(texture));
+                });
+            return;
+        }
+
+        let size = self.size.map(|d| d.max(1));
+        let time = self.start.elapsed().as_secs_f32();
+        self.texture
+            .render(device, queue, size, |encoder, texture| {
+                self.backdrop_pipeline.draw(
+                    queue,
+                    encoder,
+                    &level_view(texture),
+                    backdrop,
+                    size,
+                    time,
+                );
+            });
+    }
+    /// Replaces the wallpaper with the image at `path`.
+    /// On error the current wallpaper is kept.
+    pub fn load(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<(), WallpaperError> {
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let backdrop = read_backdrop(path.as_ref(), max_dimension)?;
+        self.set_backdrop(device, queue, backdrop);
+        Ok(())
+    }
+    pub fn set_fit(&self, queue: &wgpu::Queue, fit: WallpaperFit) {
+        queue.write_buffer(&self.texture.fit_buffer, 0, bytemuck::bytes_of(&fit));
+    }
+}
 
-        Self { pipeline, texture }
+/// Draws the renderer's wallpaper, so it can be hidden or reordered like any other layer.
+/// The wallpaper itself stays with the renderer, since every glass layer refracts it.
+pub struct WallpaperLayer;
+impl Layer for WallpaperLayer {
+    fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        system: &SystemGroup,
+        wallpaper: &Wallpaper,
+    ) {
+        wallpaper.draw(render_pass, system);
+    }
+}
+
+EnumShaderConstants!(
+    /// How the wallpaper is laid out in the viewport, matching `util/wallpaper_fit.wesl`
+    #[derive(Default, serde::Serialize, serde::Deserialize)]
+    pub enum WallpaperFit {
+        /// Scaled to fill the viewport, cropping whichever sides overflow
+        #[default]
+        Cover = 0,
+        /// Scaled to fit inside the viewport, leaving transparent bars
+        Contain = 1,
+        /// Scaled to the viewport on each axis, ignoring the aspect ratio
+        Stretch = 2,
+        /// Repeated at its native size from the center
+        Tile = 3,
+        /// Drawn once at its native size in the center
+        Center = 4,
+    }
+);
+impl FromStr for WallpaperFit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cover" => Ok(Self::Cover),
+            "contain" => Ok(Self::Contain),
+            "stretch" => Ok(Self::Stretch),
+            "tile" => Ok(Self::Tile),
+            "center" => Ok(Self::Center),
+            _ => Err(format!(
+                "unknown wallpaper fit {s}, expected cover, contain, stretch, tile or center"
+            )),
+        }
     }
 }
 pub struct WallpaperTexture {
     pub bind_group_layout: wgpu::BindGroupLayout,
     pub bind_group: wgpu::BindGroup,
+    texture: wgpu::Texture,
+    fit_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    mipmaps: Downsample,
+    blur: BackdropBlur,
 }
 impl WallpaperTexture {
     fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
         let wallpaper_bytes = include_bytes!("905114.png");
-        let wallpaper_image = image::load_from_memory(wallpaper_bytes).unwrap();
+        let wallpaper_image = image::load_from_memory(wallpaper_bytes)
+            .expect("the built-in wallpaper is a valid PNG");
         let wallpaper_rgba = wallpaper_image.to_rgba8();
 
-        use image::GenericImageView;
-        let (width, height) = wallpaper_image.dimensions();
-
-        let texture_size = wgpu::Extent3d {
-            width,
-            height,
-            // All textures are stored as 3D, we represent our 2D texture
-            // by setting depth to 1.
-            depth_or_array_layers: 1,
-        };
-
-        let wallpaper_texture = device.create_texture(&wgpu::TextureDescriptor {
-            size: texture_size,
-            mip_level_count: 1, // We'll talk about this a little later
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            // Most images are stored using sRGB, so we need to reflect that here.
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            // TEXTURE_BINDING tells wgpu that we want to use this texture in shaders
-            // COPY_DST means that we want to copy data to this texture
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            label: Some("wallpaper texture"),
-            // This is the same as with the SurfaceConfig. It
-            // specifies what texture formats can be used to
-            // create TextureViews for this texture. The base
-            // texture format (Rgba8UnormSrgb in this case) is
-            // always supported. Note that using a different
-            // texture format is not supported on the WebGL2
-            // backend.
-            view_formats: &[],
+        let mipmaps = Downsample::new(device, Self::FORMAT, DownsampleFilter::Box);
+        let wallpaper_texture = Self::upload(device, queue, &mipmaps, &wallpaper_rgba);
+        let blur = BackdropBlur::new(device, queue, &wallpaper_texture);
+        let fit_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wallpaper fit buffer"),
+            contents: bytemuck::bytes_of(&WallpaperFit::default()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        queue.write_texture(
-            // Tells wgpu where to copy the pixel data
-            wgpu::TexelCopyTextureInfo {
-                texture: &wallpaper_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            // The actual pixel data
-            &wallpaper_rgba,
-            // The layout of the texture
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            texture_size,
-        );
-
-        let wallpaper_texture_view =
-            wallpaper_texture.create_view(&wgpu::TextureViewDescriptor::default());
         let wallpaper_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
@@ -170,12 +400,207 @@ impl WallpaperTexture {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
                 label: Some("texture_bind_group_layout"),
             });
 
-        let wallpaper_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
+        let wallpaper_bind_group = Self::bind_group(
+            device,
+            &texture_bind_group_layout,
+            &wallpaper_texture,
+            &wallpaper_sampler,
+            &blur,
+            &fit_buffer,
+        );
+
+        Self {
+            bind_group_layout: texture_bind_group_layout,
+            bind_group: wallpaper_bind_group,
+            texture: wallpaper_texture,
+            fit_buffer,
+            sampler: wallpaper_sampler,
+            mipmaps,
+            blur,
+        }
+    }
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    /// Swaps in a new image, keeping the bind group layout so pipelines built against it stay valid
+    /// Images the same size as the current one are written in place, so animation frames
+    /// don't reallocate anything.
+    fn replace(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, image: &image::RgbaImage) {
+        let (width, height) = image.dimensions();
+        self.render(device, queue, [width, height], |_, texture| {
+            Self::write_image(queue, texture, image);
+        });
+    }
+    /// Fills level 0 with `draw` and refreshes the mips and blur from it.
+    /// The texture is only reallocated, and the bind group rebuilt, when `size` changes.
+    fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        [width, height]: [u32; 2],
+        draw: impl FnOnce(&mut wgpu::CommandEncoder, &wgpu::Texture),
+    ) {
+        let resized = self.texture.width() != width || self.texture.height() != height;
+        if resized {
+            self.texture = Self::create_texture(device, width, height);
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("wallpaper render encoder"),
+        });
+        draw(&mut encoder, &self.texture);
+        self.mipmaps.generate(
+            device,
+            &mut encoder,
+            &self.texture,
+            1..self.texture.mip_level_count(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.blur.set_source(device, queue, &self.texture);
+        if resized {
+            self.rebuild_bind_group(device);
+        }
+    }
+    fn rebuild_bind_group(&mut self, device: &wgpu::Device) {
+        self.bind_group = Self::bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.texture,
+            &self.sampler,
+            &self.blur,
+            &self.fit_buffer,
+        );
+    }
+    /// Creates the texture for `image` and fills in its mip chain
+    fn upload(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mipmaps: &Downsample,
+        image: &image::RgbaImage,
+    ) -> wgpu::Texture {
+        let (width, height) = image.dimensions();
+        let wallpaper_texture = Self::create_texture(device, width, height);
+        Self::write_image(queue, &wallpaper_texture, image);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("wallpaper mipmap encoder"),
+        });
+        mipmaps.generate(
+            device,
+            &mut encoder,
+            &wallpaper_texture,
+            1..wallpaper_texture.mip_level_count(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        wallpaper_texture
+    }
+    /// Writes `image` into level 0 of `texture`, which must be the same size
+    fn write_image(queue: &wgpu::Queue, texture: &wgpu::Texture, image: &image::RgbaImage) {
+        let (width, height) = image.dimensions();
+        queue.write_texture(
+            // Tells wgpu where to copy the pixel data
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            // The actual pixel data
+            image,
+            // The layout of the texture
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+    /// Allocates a wallpaper texture with room for a full mip chain
+    fn create_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+        let texture_size = wgpu::Extent3d {
+            width,
+            height,
+            // All textures are stored as 3D, we represent our 2D texture
+            // by setting depth to 1.
+            depth_or_array_layers: 1,
+        };
+
+        device.create_texture(&wgpu::TextureDescriptor {
+            size: texture_size,
+            mip_level_count: full_mip_level_count(texture_size),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // Most images are stored using sRGB, so we need to reflect that here.
+            format: Self::FORMAT,
+            // TEXTURE_BINDING tells wgpu that we want to use this texture in shaders
+            // COPY_DST means that we want to copy data to this texture
+            // COPY_SRC lets the backdrop blur pyramid start from a copy of it
+            // RENDER_ATTACHMENT lets the mip levels and procedural backdrops be rendered into it
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("wallpaper texture"),
+            // This is the same as with the SurfaceConfig. It
+            // specifies what texture formats can be used to
+            // create TextureViews for this texture. The base
+            // texture format (Rgba8UnormSrgb in this case) is
+            // always supported. Note that using a different
+            // texture format is not supported on the WebGL2
+            // backend.
+            view_formats: &[],
+        })
+    }
+    fn bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        wallpaper_texture: &wgpu::Texture,
+        wallpaper_sampler: &wgpu::Sampler,
+        blur: &BackdropBlur,
+        fit_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        let wallpaper_texture_view =
+            wallpaper_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let blur_view = blur.view();
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -183,15 +608,158 @@ impl WallpaperTexture {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&wallpaper_sampler),
+                    resource: wgpu::BindingResource::Sampler(wallpaper_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&blur_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(blur.sampler()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: fit_buffer.as_entire_binding(),
                 },
             ],
             label: Some("wallpaper_bind_group"),
-        });
+        })
+    }
+}
 
-        Self {
-            bind_group_layout: texture_bind_group_layout,
-            bind_group: wallpaper_bind_group,
+#[derive(Debug)]
+pub enum WallpaperError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    UnsupportedFormat {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    Decode {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    /// Either side exceeds the device's `max_texture_dimension_2d`
+    TooLarge {
+        path: PathBuf,
+        size: [u32; 2],
+        max_dimension: u32,
+    },
+    /// An animated image without any frames
+    NoFrames {
+        path: PathBuf,
+    },
+}
+impl fmt::Display for WallpaperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { path, source } => {
+                write!(f, "couldn't read wallpaper {}: {source}", path.display())
+            }
+            Self::UnsupportedFormat { path, source } => {
+                write!(
+                    f,
+                    "unsupported wallpaper format {}: {source}",
+                    path.display()
+                )
+            }
+            Self::Decode { path, source } => {
+                write!(f, "couldn't decode wallpaper {}: {source}", path.display())
+            }
+            Self::TooLarge {
+                path,
+                size: [width, height],
+                max_dimension,
+            } => write!(
+                f,
+                "wallpaper {} is {width}x{height}, larger than the maximum texture size of {max_dimension}",
+                path.display()
+            ),
+            Self::NoFrames { path } => write!(f, "wallpaper {} has no frames", path.display()),
         }
     }
 }
+impl Error for WallpaperError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Read { source, .. } => Some(source),
+            Self::UnsupportedFormat { source, .. } | Self::Decode { source, .. } => Some(source),
+            Self::TooLarge { .. } | Self::NoFrames { .. } => None,
+        }
+    }
+}
+
+/// Decodes the image at `path`, keeping every frame of animated GIFs and APNGs
+fn read_backdrop(path: &Path, max_dimension: u32) -> Result<Backdrop, WallpaperError> {
+    let read_error = |source| WallpaperError::Read {
+        path: path.to_owned(),
+        source,
+    };
+    let decode_error = |source| match source {
+        image::ImageError::Unsupported(_) => WallpaperError::UnsupportedFormat {
+            path: path.to_owned(),
+            source,
+        },
+        image::ImageError::IoError(source) => read_error(source),
+        source => WallpaperError::Decode {
+            path: path.to_owned(),
+            source,
+        },
+    };
+    let check_size = |(width, height)| {
+        if width > max_dimension || height > max_dimension {
+            return Err(WallpaperError::TooLarge {
+                path: path.to_owned(),
+                size: [width, height],
+                max_dimension,
+            });
+        }
+        Ok(())
+    };
+
+    let open = || -> Result<_, WallpaperError> {
+        image::io::Reader::open(path)
+            .map_err(read_error)?
+            .with_guessed_format()
+            .map_err(read_error)
+    };
+    let reader = open()?;
+    let frames = match reader.format() {
+        Some(image::ImageFormat::Gif) => {
+            let decoder = GifDecoder::new(reader.into_inner()).map_err(decode_error)?;
+            check_size(decoder.dimensions())?;
+            decoder.into_frames()
+        }
+        Some(image::ImageFormat::Png) => {
+            let decoder = PngDecoder::new(reader.into_inner()).map_err(decode_error)?;
+            check_size(decoder.dimensions())?;
+            if !decoder.is_apng() {
+                let image = image::DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+                return Ok(Backdrop::Image(image.to_rgba8()));
+            }
+            decoder.apng().into_frames()
+        }
+        _ => {
+            // Only the header is read here, so oversized images are rejected before
+            // they're decoded, then the file is read again to decode it
+            check_size(reader.into_dimensions().map_err(decode_error)?)?;
+            let image = open()?.decode().map_err(decode_error)?;
+            return Ok(Backdrop::Image(image.to_rgba8()));
+        }
+    };
+
+    let mut frames = frames
+        .map(|frame| frame.map(AnimationFrame::from))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(decode_error)?;
+    match frames.len() {
+        0 => Err(WallpaperError::NoFrames {
+            path: path.to_owned(),
+        }),
+        1 => Ok(Backdrop::Image(frames.remove(0).image)),
+        _ => Ok(Backdrop::Animation(frames)),
+    }
+}
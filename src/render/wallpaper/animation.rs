@@ -0,0 +1,180 @@
+use std::time::{Duration, Instant};
+
+/// One decoded frame of an animated backdrop, already composited onto the full canvas
+pub struct AnimationFrame {
+    pub image: image::RgbaImage,
+    /// How long the frame stays on screen
+    pub delay: Duration,
+}
+impl AnimationFrame {
+    /// Browsers treat very short GIF delays as unset and fall back to 100ms, so do the same
+    const MIN_DELAY: Duration = Duration::from_millis(20);
+    const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+}
+impl From<image::Frame> for AnimationFrame {
+    fn from(frame: image::Frame) -> Self {
+        let delay = Duration::from(frame.delay());
+        Self {
+            image: frame.into_buffer(),
+            delay: if delay < Self::MIN_DELAY {
+                Self::DEFAULT_DELAY
+            } else {
+                delay
+            },
+        }
+    }
+}
+
+/// Tracks which frame of an animated backdrop is showing
+pub struct Playback {
+    frames: Vec<AnimationFrame>,
+    current: usize,
+    frame_start: Instant,
+    /// Time already spent on the current frame while paused
+    paused: Option<Duration>,
+    looping: bool,
+    /// Paused on the last frame because looping is off
+    finished: bool,
+    /// Rewound by [`Playback::play`] since the last advance, which reports it as a change
+    rewound: bool,
+}
+impl Playback {
+    pub fn current_frame(&self) -> &image::RgbaImage {
+        &self.frames[self.current].image
+    }
+    pub fn is_playing(&self) -> bool {
+        self.paused.is_none()
+    }
+    /// Resumes where playback paused, or starts over once a non-looping animation has
+    /// finished
+    pub fn play(&mut self) {
+        if self.finished {
+            self.finished = false;
+            self.rewound = self.current != 0;
+            self.current = 0;
+            self.paused = None;
+            self.frame_start = Instant::now();
+        } else if let Some(elapsed) = self.paused.take() {
+            self.frame_start = Instant::now() - elapsed;
+        }
+    }
+    pub fn pause(&mut self) {
+        if self.paused.is_none() {
+            self.paused = Some(self.frame_start.elapsed());
+        }
+    }
+    /// Without looping, playback pauses on the last frame
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Steps past every frame whose delay has run out by `now`, returning whether the
+    /// current frame changed
+    pub fn advance(&mut self, now: Instant) -> bool {
+        let rewound = std::mem::take(&mut self.rewound);
+        if self.paused.is_some() {
+            return rewound;
+        }
+        // Skip whole loops after a stall instead of stepping through them
+        let total: Duration = self.frames.iter().map(|frame| frame.delay).sum();
+        if now.saturating_duration_since(self.frame_start) > total {
+            self.frame_start = now - self.frames[self.current].delay;
+        }
+
+        let start = self.current;
+        loop {
+            let delay = self.frames[self.current].delay;
+            if now.saturating_duration_since(self.frame_start) < delay {
+                break;
+            }
+            let next = (self.current + 1) % self.frames.len();
+            if next == 0 && !self.looping {
+                self.paused = Some(Duration::ZERO);
+                self.finished = true;
+                break;
+            }
+            self.frame_start += delay;
+            self.current = next;
+        }
+        self.current != start || rewound
+    }
+
+    /// `frames` must not be empty
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        assert!(!frames.is_empty(), "an animation needs at least one frame");
+        Self {
+            frames,
+            current: 0,
+            frame_start: Instant::now(),
+            paused: None,
+            looping: true,
+            finished: false,
+            rewound: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playback(frames: usize) -> Playback {
+        let frames = (0..frames)
+            .map(|_| AnimationFrame {
+                image: image::RgbaImage::new(1, 1),
+                delay: Duration::from_millis(100),
+            })
+            .collect();
+        Playback::new(frames)
+    }
+
+    #[test]
+    fn advances_and_loops() {
+        let mut playback = playback(3);
+        let start = playback.frame_start;
+        assert!(!playback.advance(start + Duration::from_millis(50)));
+        assert!(playback.advance(start + Duration::from_millis(150)));
+        assert_eq!(playback.current, 1);
+        assert!(playback.advance(start + Duration::from_millis(310)));
+        assert_eq!(playback.current, 0);
+    }
+
+    #[test]
+    fn stops_on_the_last_frame_without_looping() {
+        let mut playback = playback(3);
+        playback.set_looping(false);
+        let start = playback.frame_start;
+        playback.advance(start + Duration::from_millis(290));
+        assert_eq!(playback.current, 2);
+        assert!(!playback.advance(start + Duration::from_millis(310)));
+        assert_eq!(playback.current, 2);
+        assert!(!playback.is_playing());
+    }
+
+    #[test]
+    fn play_restarts_a_finished_animation() {
+        let mut playback = playback(3);
+        playback.set_looping(false);
+        let start = playback.frame_start;
+        playback.advance(start + Duration::from_millis(290));
+        playback.advance(start + Duration::from_millis(310));
+        assert!(!playback.is_playing());
+
+        playback.play();
+        assert!(playback.is_playing());
+        assert_eq!(playback.current, 0);
+        // The rewind itself counts as a change, so the first frame gets uploaded again
+        assert!(playback.advance(playback.frame_start));
+        assert!(!playback.advance(playback.frame_start + Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn play_resumes_a_paused_animation_in_place() {
+        let mut playback = playback(3);
+        let start = playback.frame_start;
+        playback.advance(start + Duration::from_millis(150));
+        playback.pause();
+        playback.play();
+        assert_eq!(playback.current, 1);
+    }
+}
@@ -0,0 +1,294 @@
+use wesl::include_wesl;
+use wgpu::util::DeviceExt;
+
+use super::animation::AnimationFrame;
+use crate::render::shape::component::{RGBA, vec2f};
+
+/// What the wallpaper shows, either a bitmap or a pattern rendered on the GPU.
+/// Procedural backdrops are rendered at the viewport size into the same texture an image
+/// would be uploaded to, so everything sampling the wallpaper works with either.
+pub enum Backdrop {
+    Image(image::RgbaImage),
+    /// Frames of an animated GIF or APNG, all the same size
+    Animation(Vec<AnimationFrame>),
+    Solid(RGBA),
+    /// Gradient along the line from `start` to `end`, both in texture coordinates
+    LinearGradient {
+        start: vec2f,
+        end: vec2f,
+        stops: Vec<GradientStop>,
+    },
+    /// Gradient outwards from `center` in texture coordinates,
+    /// reaching the last stop at `radius` times the longer side
+    RadialGradient {
+        center: vec2f,
+        radius: f32,
+        stops: Vec<GradientStop>,
+    },
+    /// A snapshot of any texture the caller renders, such as their own UI. The glass never
+    /// samples the texture itself. Its content is copied when set and again on each
+    /// [`Wallpaper::refresh_backdrop_snapshot`](super::Wallpaper::refresh_backdrop_snapshot),
+    /// which also rebuilds the mips and blur from the copy. The texture must be created
+    /// with `TextureUsages::TEXTURE_BINDING` and a filterable float format.
+    TextureSnapshot {
+        view: wgpu::TextureView,
+        size: [u32; 2],
+        color_space: BackdropColorSpace,
+    },
+    /// Drifting fractal noise blending between two colors
+    Noise {
+        colors: [RGBA; 2],
+        /// Noise cells across the longer side
+        scale: f32,
+        /// Noise cells travelled per second
+        speed: f32,
+    },
+}
+/// What sampling the texture of a [`Backdrop::TextureSnapshot`] returns
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BackdropColorSpace {
+    /// Linear values, from `*Srgb` formats which decode on sampling or from float formats
+    #[default]
+    Linear,
+    /// sRGB encoded values stored in a non-sRGB format such as `Bgra8Unorm`
+    Srgb,
+}
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GradientStop {
+    /// Position along the gradient from 0 to 1
+    pub offset: f32,
+    pub color: RGBA,
+}
+impl Backdrop {
+    /// Whether the backdrop can change from one frame to the next
+    pub fn is_animated(&self) -> bool {
+        matches!(self, Self::Animation(_) | Self::Noise { .. })
+    }
+    fn uniform(&self, size: [u32; 2], time: f32) -> BackdropUniform {
+        let mut uniform = BackdropUniform {
+            kind: BackdropKind::Solid,
+            stop_count: 0,
+            time,
+            scale: 0.0,
+            size: size.map(|d| d as f32),
+            _padding: [0; 2],
+            points: [0.0; 4],
+            stops: [GradientStopUniform::zeroed(); MAX_GRADIENT_STOPS],
+        };
+        // Solid and noise colors travel as stops without offsets
+        let mut colors = [GradientStop {
+            offset: 0.0,
+            color: 0.into(),
+        }; 2];
+        let stops: &[GradientStop] = match self {
+            Self::Image(_) | Self::Animation(_) | Self::TextureSnapshot { .. } => {
+                unreachable!("images and textures are copied, not rendered")
+            }
+            Self::Solid(color) => {
+                uniform.kind = BackdropKind::Solid;
+                colors[0].color = *color;
+                &colors[..1]
+            }
+            Self::LinearGradient { start, end, stops } => {
+                uniform.kind = BackdropKind::LinearGradient;
+                uniform.points = [start[0], start[1], end[0], end[1]];
+                stops
+            }
+            Self::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                uniform.kind = BackdropKind::RadialGradient;
+                uniform.points = [center[0], center[1], *radius, 0.0];
+                stops
+            }
+            Self::Noise {
+                colors: noise_colors,
+                scale,
+                speed,
+            } => {
+                uniform.kind = BackdropKind::Noise;
+                uniform.scale = *scale;
+                uniform.time = time * speed;
+                colors[0].color = noise_colors[0];
+                colors[1].color = noise_colors[1];
+                &colors
+            }
+        };
+        if stops.len() > MAX_GRADIENT_STOPS {
+            log::warn!(
+                "backdrop has {} gradient stops, only the first {MAX_GRADIENT_STOPS} are used",
+                stops.len()
+            );
+        }
+        for (uniform_stop, stop) in uniform.stops.iter_mut().zip(stops) {
+            *uniform_stop = GradientStopUniform {
+                color: stop.color,
+                offset: stop.offset,
+                _padding: [0; 3],
+            };
+        }
+        uniform.stop_count = stops.len().min(MAX_GRADIENT_STOPS) as _;
+        uniform
+    }
+}
+
+const MAX_GRADIENT_STOPS: usize = 8;
+
+EnumShaderConstants!(
+    enum BackdropKind {
+        Solid = 0,
+        LinearGradient = 1,
+        RadialGradient = 2,
+        Noise = 3,
+    }
+);
+
+#[derive(Copy, Clone, Debug, bytemuck::NoUninit)]
+#[repr(C)]
+struct BackdropUniform {
+    kind: BackdropKind,
+    stop_count: u32,
+    time: f32,
+    scale: f32,
+    size: vec2f,
+    _padding: [u32; 2],
+    points: [f32; 4],
+    stops: [GradientStopUniform; MAX_GRADIENT_STOPS],
+}
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct GradientStopUniform {
+    color: RGBA,
+    offset: f32,
+    _padding: [u32; 3],
+}
+impl GradientStopUniform {
+    fn zeroed() -> Self {
+        bytemuck::Zeroable::zeroed()
+    }
+}
+
+/// Renders procedural [`Backdrop`]s into a texture
+pub struct BackdropPipeline {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+impl BackdropPipeline {
+    pub fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        backdrop: &Backdrop,
+        size: [u32; 2],
+        time: f32,
+    ) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&backdrop.uniform(size, time)),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("backdrop render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("backdrop uniform buffer"),
+            contents: bytemuck::bytes_of(&Backdrop::Solid(0.into()).uniform([1; 2], 0.0)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("backdrop bind group layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("backdrop bind group"),
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("backdrop shader"),
+            source: wgpu::ShaderSource::Wgsl(include_wesl!("backdrop").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("backdrop pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("backdrop pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: Default::default(),
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Cw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use super::downsample::{Downsample, DownsampleFilter};
+
+/// Progressively blurred copies of the backdrop, one per mip level of a single texture.
+/// Level 0 is the sharp backdrop and every following level is a dual filter downsample of
+/// the one before it, so sampling at level `n` blurs by roughly `2^n` texels. The glass
+/// shader magnifies the coarse levels with a cubic B-spline rather than bilinearly, which
+/// stands in for the dual filter's upsample passes.
+pub struct BackdropBlur {
+    downsample: Downsample,
+    pyramid: wgpu::Texture,
+}
+impl BackdropBlur {
+    const MAX_LEVELS: u32 = 7;
+
+    pub fn view(&self) -> wgpu::TextureView {
+        self.pyramid
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        self.downsample.sampler()
+    }
+
+    /// Rebuilds every level of the pyramid from `source`, which must match its size and format
+    /// and allow `COPY_SRC`
+    pub fn generate(&self, device: &wgpu::Device, queue: &wgpu::Queue, source: &wgpu::Texture) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("backdrop blur encoder"),
+        });
+        encoder.copy_texture_to_texture(
+            source.as_image_copy(),
+            self.pyramid.as_image_copy(),
+            self.pyramid.size(),
+        );
+        self.downsample.generate(
+            device,
+            &mut encoder,
+            &self.pyramid,
+            1..self.pyramid.mip_level_count(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Regenerates the pyramid from a new backdrop, reallocating it if the size changed
+    pub fn set_source(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        source: &wgpu::Texture,
+    ) {
+        if source.size() != self.pyramid.size() {
+            self.pyramid = Self::create_pyramid(device, source);
+        }
+        self.generate(device, queue, source);
+    }
+    fn create_pyramid(device: &wgpu::Device, source: &wgpu::Texture) -> wgpu::Texture {
+        let size = source.size();
+        // Stop before the smallest dimension would round down to zero
+        let level_count = Self::MAX_LEVELS.min(32 - size.width.min(size.height).leading_zeros());
+
+        device.create_texture(&wgpu::TextureDescriptor {
+            size,
+            mip_level_count: level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: source.format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_DST,
+            label: Some("backdrop blur pyramid"),
+            view_formats: &[],
+        })
+    }
+
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, source: &wgpu::Texture) -> Self {
+        let pyramid = Self::create_pyramid(device, source);
+        let downsample = Downsample::new(device, source.format(), DownsampleFilter::DualFilter);
+
+        let blur = Self {
+            downsample,
+            pyramid,
+        };
+        blur.generate(device, queue, source);
+        blur
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Notices when a file, or any file under a directory, is saved by polling modification
+/// times. That's cheap enough for a scene file or the shader sources and needs no
+/// watcher thread.
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+impl Watcher {
+    const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Whether anything was modified since the last time this returned `true`. A missing
+    /// file doesn't count, since editors that save by renaming briefly remove it.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < Self::POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        let modified = last_modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+
+    /// Changes from before the watcher was created aren't reported
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            modified: last_modified(&path),
+            path,
+            last_poll: Instant::now(),
+        }
+    }
+}
+
+/// The latest modification time of `path` or, for a directory, anything inside it
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_dir() {
+        return metadata.modified().ok();
+    }
+    fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| last_modified(&entry.ok()?.path()))
+        .max()
+}
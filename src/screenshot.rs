@@ -0,0 +1,51 @@
+use std::{error::Error, fmt, path::PathBuf};
+
+use crate::{
+    args::Args,
+    render::{Offscreen, OffscreenError, Renderer, headless_device},
+};
+
+/// Renders one frame of the demo scene offscreen and saves it, for `--screenshot`
+pub fn run(args: &Args, path: PathBuf) -> Result<(), ScreenshotError> {
+    let (device, queue) =
+        pollster::block_on(headless_device(args.software)).map_err(ScreenshotError::Render)?;
+    let offscreen = Offscreen::new(&device, args.size, wgpu::TextureFormat::Rgba8Unorm)
+        .map_err(ScreenshotError::Render)?;
+
+    let mut renderer = Renderer::new(&device, &queue, &offscreen.config());
+    args.configure(&mut renderer, &device, &queue);
+
+    let image = offscreen
+        .render(&device, &queue, &mut renderer)
+        .map_err(ScreenshotError::Render)?;
+    image
+        .save_with_format(&path, image::ImageFormat::Png)
+        .map_err(|source| ScreenshotError::Save { path, source })
+}
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    Render(OffscreenError),
+    Save {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+}
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Render(err) => write!(f, "{err}"),
+            Self::Save { path, source } => {
+                write!(f, "couldn't save screenshot {}: {source}", path.display())
+            }
+        }
+    }
+}
+impl Error for ScreenshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Render(err) => Some(err),
+            Self::Save { source, .. } => Some(source),
+        }
+    }
+}